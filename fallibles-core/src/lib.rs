@@ -12,8 +12,59 @@ extern crate std;
 use std::time::Duration;
 
 use alloc::boxed::Box;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use core::task::{Context, Poll};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::boxed::Box;
+    pub use fxhash;
+
+    pub use crate::PointScope;
+
+    use crate::FailurePoint;
+
+    #[cfg(feature = "registry")]
+    pub use linkme;
+
+    /// Every `#[fallible]` point in the program, read by `all_points()`.
+    #[cfg(feature = "registry")]
+    #[linkme::distributed_slice]
+    pub static POINTS: [FailurePoint];
+
+    /// An injected error about to be returned, for `on_failure_value()` hooks.
+    ///
+    /// Calling `(&ErrorValue(&err)).report(fp)` with both traits in scope picks
+    /// `ReportDebug` when the error is `Debug` and the no-op `ReportOpaque` otherwise.
+    pub struct ErrorValue<'a, T>(pub &'a T);
+
+    pub trait ReportDebug {
+        fn report(&self, fp: FailurePoint);
+    }
+
+    impl<T: core::fmt::Debug> ReportDebug for ErrorValue<'_, T> {
+        fn report(&self, fp: FailurePoint) {
+            crate::report_failure_value(fp, self.0);
+        }
+    }
+
+    pub trait ReportOpaque {
+        fn report(&self, fp: FailurePoint);
+    }
+
+    impl<T> ReportOpaque for &ErrorValue<'_, T> {
+        fn report(&self, _fp: FailurePoint) {}
+    }
+
+    /// Whether this crate was built with `debug_assertions`, which decides whether
+    /// configs without `force_in_release(true)` are installed. Doctests are always
+    /// built with them, so they check this instead of `cfg!(debug_assertions)`.
+    pub const DEBUG_ASSERTIONS: bool = cfg!(debug_assertions);
+}
 
 /// Trait for error types that can be generated during simulated failures.
 ///
@@ -35,6 +86,38 @@ use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 /// ```
 pub trait FallibleError {
     fn simulated_failure() -> Self;
+
+    /// Build the error for a specific failure point.
+    ///
+    /// This is what `#[fallible]` calls when a failure triggers. It defaults to
+    /// `simulated_failure()`; override it to vary the error by function, file or line.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::{FailurePoint, FailurePointId, FallibleError};
+    ///
+    /// #[derive(Debug)]
+    /// struct MyError { message: String }
+    ///
+    /// impl FallibleError for MyError {
+    ///     fn simulated_failure() -> Self {
+    ///         MyError { message: "test failure".to_string() }
+    ///     }
+    ///
+    ///     fn simulated_failure_for(fp: FailurePoint) -> Self {
+    ///         MyError { message: format!("test failure at line {}", fp.line) }
+    ///     }
+    /// }
+    ///
+    /// let fp = FailurePoint { line: 42, column: 1, ..FailurePoint::new(FailurePointId(1), "load") };
+    /// assert_eq!(MyError::simulated_failure_for(fp).message, "test failure at line 42");
+    /// ```
+    fn simulated_failure_for(_fp: FailurePoint) -> Self
+    where
+        Self: Sized,
+    {
+        Self::simulated_failure()
+    }
 }
 
 impl FallibleError for &'static str {
@@ -47,18 +130,74 @@ impl FallibleError for alloc::string::String {
     fn simulated_failure() -> Self {
         alloc::string::String::from("simulated failure")
     }
+
+    /// Names the function and location that failed.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::{FailurePoint, FailurePointId, FallibleError};
+    ///
+    /// let fp = FailurePoint { file: "src/users.rs", line: 42, column: 5, ..FailurePoint::new(FailurePointId(1), "load_user") };
+    /// let message = String::simulated_failure_for(fp);
+    /// assert_eq!(message, "simulated failure in load_user at src/users.rs:42:5");
+    /// ```
+    fn simulated_failure_for(fp: FailurePoint) -> Self {
+        failure_message(fp)
+    }
+}
+
+fn failure_message(fp: FailurePoint) -> alloc::string::String {
+    alloc::format!("simulated failure in {} at {}:{}:{}", fp.function, fp.file, fp.line, fp.column)
 }
 
 impl<T: FallibleError> FallibleError for alloc::boxed::Box<T> {
     fn simulated_failure() -> Self {
         alloc::boxed::Box::new(T::simulated_failure())
     }
+
+    fn simulated_failure_for(fp: FailurePoint) -> Self {
+        alloc::boxed::Box::new(T::simulated_failure_for(fp))
+    }
+}
+
+/// Boxed trait objects hold a plain message error.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::*;
+/// use std::error::Error;
+///
+/// let err = <Box<dyn Error + Send + Sync>>::simulated_failure();
+/// assert_eq!(err.to_string(), "simulated failure");
+/// ```
+impl FallibleError for alloc::boxed::Box<dyn core::error::Error + Send + Sync> {
+    fn simulated_failure() -> Self {
+        "simulated failure".into()
+    }
+
+    fn simulated_failure_for(fp: FailurePoint) -> Self {
+        failure_message(fp).into()
+    }
+}
+
+impl FallibleError for alloc::boxed::Box<dyn core::error::Error> {
+    fn simulated_failure() -> Self {
+        "simulated failure".into()
+    }
+
+    fn simulated_failure_for(fp: FailurePoint) -> Self {
+        failure_message(fp).into()
+    }
 }
 
 #[cfg(feature = "std")]
 impl FallibleError for std::io::Error {
     fn simulated_failure() -> Self {
-        std::io::Error::new(std::io::ErrorKind::Other, "simulated failure")
+        std::io::Error::other("simulated failure")
+    }
+
+    fn simulated_failure_for(fp: FailurePoint) -> Self {
+        std::io::Error::other(failure_message(fp))
     }
 }
 
@@ -67,6 +206,10 @@ impl FallibleError for anyhow::Error {
     fn simulated_failure() -> Self {
         anyhow::anyhow!("simulated failure")
     }
+
+    fn simulated_failure_for(fp: FailurePoint) -> Self {
+        anyhow::Error::msg(failure_message(fp))
+    }
 }
 
 #[cfg(feature = "eyre")]
@@ -74,6 +217,53 @@ impl FallibleError for eyre::Report {
     fn simulated_failure() -> Self {
         eyre::eyre!("simulated failure")
     }
+
+    fn simulated_failure_for(fp: FailurePoint) -> Self {
+        eyre::Report::msg(failure_message(fp))
+    }
+}
+
+/// Error type that records which failure point produced it.
+///
+/// Use it as the error type of `#[fallible]` functions to keep the origin of an
+/// injected failure attached while it travels through `?`, including into a
+/// `Box<dyn Error>` where it can be recovered with `downcast_ref`.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::*;
+///
+/// let fp = FailurePoint { file: "src/users.rs", line: 42, column: 5, ..FailurePoint::new(FailurePointId(1), "load_user") };
+/// let err = SimulatedFailure::simulated_failure_for(fp);
+/// assert_eq!(err.point.map(|fp| fp.line), Some(42));
+/// assert_eq!(err.to_string(), "simulated failure in load_user at src/users.rs:42:5");
+/// assert!(SimulatedFailure::simulated_failure().point.is_none());
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct SimulatedFailure {
+    /// The point that failed, `None` when built without one by `simulated_failure()`
+    pub point: Option<FailurePoint>,
+}
+
+impl core::fmt::Display for SimulatedFailure {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.point {
+            Some(fp) => f.write_str(&failure_message(fp)),
+            None => f.write_str("simulated failure"),
+        }
+    }
+}
+
+impl core::error::Error for SimulatedFailure {}
+
+impl FallibleError for SimulatedFailure {
+    fn simulated_failure() -> Self {
+        SimulatedFailure { point: None }
+    }
+
+    fn simulated_failure_for(fp: FailurePoint) -> Self {
+        SimulatedFailure { point: Some(fp) }
+    }
 }
 
 impl FallibleError for () {
@@ -92,6 +282,216 @@ impl<T> FallibleError for Option<T> {
     }
 }
 
+/// Trait for success values that `#[fallible(poison = true)]` returns instead of an error.
+///
+/// A poisoned value is degenerate but valid, like `0`, `NaN` or an empty string, for
+/// testing code that trusts results without validating them.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::PoisonValue;
+///
+/// struct Price(u64);
+///
+/// impl PoisonValue for Price {
+///     fn poisoned() -> Self {
+///         Price(0)
+///     }
+/// }
+///
+/// assert!(f64::poisoned().is_nan());
+/// assert_eq!(Price::poisoned().0, 0);
+/// ```
+pub trait PoisonValue {
+    fn poisoned() -> Self;
+}
+
+macro_rules! impl_poison_value {
+    ($value:expr => $($ty:ty),*) => {
+        $(
+            impl PoisonValue for $ty {
+                fn poisoned() -> Self {
+                    $value
+                }
+            }
+        )*
+    };
+}
+
+impl_poison_value!(0 => u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+impl_poison_value!(Self::NAN => f32, f64);
+impl_poison_value!(false => bool);
+impl_poison_value!(() => ());
+impl_poison_value!("" => &'static str);
+impl_poison_value!(alloc::string::String::new() => alloc::string::String);
+
+impl<T> PoisonValue for Vec<T> {
+    fn poisoned() -> Self {
+        Vec::new()
+    }
+}
+
+impl<T> PoisonValue for Option<T> {
+    fn poisoned() -> Self {
+        None
+    }
+}
+
+/// Inject a failure at a single step inside a function.
+///
+/// Where `#[fallible]` guards a whole function, `fallible_step!` marks one line of it.
+/// When the step's failure point triggers, the enclosing function returns
+/// `Err(FallibleError::simulated_failure_for(fp))` early; the error type is inferred
+/// from its return type. The point's id is derived from `name` like a function's, so
+/// a step can be targeted with `enable_named(name)`.
+///
+/// Like `#[fallible]`, the guard expands to nothing unless the calling crate enables
+/// its `fallibles-sim` feature.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::fallible_step;
+///
+/// fn migrate() -> Result<(), String> {
+///     fallible_step!("migrate_schema");
+///     // ...
+///     fallible_step!("migrate_data");
+///     // ...
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! fallible_step {
+    ($name:literal) => {
+        #[cfg(feature = "fallibles-sim")]
+        {
+            let fp = $crate::FailurePoint {
+                id: $crate::FailurePointId::from_name($name),
+                function: $name,
+                file: file!(),
+                line: line!(),
+                column: column!(),
+                severity: $crate::Severity::Medium,
+            };
+            if $crate::should_simulate_failure(fp) {
+                let err = {
+                    let _scope = $crate::__private::PointScope::build(fp);
+                    $crate::FallibleError::simulated_failure_for(fp)
+                };
+                {
+                    use $crate::__private::{ReportDebug as _, ReportOpaque as _};
+                    (&$crate::__private::ErrorValue(&err)).report(fp);
+                }
+                return Err(err);
+            }
+        }
+    };
+}
+
+/// Add a `#[fallible]` point to the slice read by `all_points()`.
+///
+/// Expands to nothing without the `registry` feature. Used by generated code.
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_point {
+    ($point:expr) => {
+        const _: () = {
+            #[$crate::__private::linkme::distributed_slice($crate::__private::POINTS)]
+            #[linkme(crate = $crate::__private::linkme)]
+            static POINT: $crate::FailurePoint = $point;
+        };
+    };
+}
+
+#[cfg(not(feature = "registry"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_point {
+    ($point:expr) => {};
+}
+
+/// List every `#[fallible]` point linked into the program, in no particular order.
+///
+/// Points are collected into one slice at link time with `linkme`, so functions
+/// show up here without being called, e.g. to document them or to toggle them
+/// from an admin page.
+///
+/// Only points compiled with `fallibles-sim` enabled are listed. A library crate the
+/// program never refers to isn't linked at all; `use that_crate as _;` brings its
+/// points in.
+#[cfg(feature = "registry")]
+pub fn all_points() -> &'static [FailurePoint] {
+    &__private::POINTS
+}
+
+/// Future returned by `#[fallible]` functions declared as `-> impl Future<Output = ..>`.
+///
+/// Either resolves immediately to the injected error or drives the original future.
+pub enum InjectedFuture<F: Future> {
+    /// A failure was injected; resolves to this output on first poll
+    Failed(Option<F::Output>),
+    /// No failure; polls the function's own future
+    Inner(F),
+}
+
+impl<F: Future> Future for InjectedFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: the inner future is never moved out of the pinned enum.
+        match unsafe { self.get_unchecked_mut() } {
+            InjectedFuture::Failed(output) => {
+                Poll::Ready(output.take().expect("InjectedFuture polled after completion"))
+            }
+            InjectedFuture::Inner(future) => unsafe { Pin::new_unchecked(future) }.poll(cx),
+        }
+    }
+}
+
+/// Check for an injected failure from inside a hand-written `Future::poll`.
+///
+/// Returns `Some(Poll::Ready(Err(..)))` when the active config fails `fp`, so `poll`
+/// can return it straight away, and `None` otherwise. Every call is one check, so
+/// call it once per operation (e.g. on the first poll) rather than on every poll.
+///
+/// # Example
+/// ```
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+/// use std::future::Future;
+/// use std::pin::{Pin, pin};
+/// use std::task::{Context, Poll, Waker};
+///
+/// struct Fetch {
+///     started: bool,
+/// }
+///
+/// impl Future for Fetch {
+///     type Output = Result<u32, String>;
+///
+///     fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+///         if !self.started {
+///             self.started = true;
+///             let fp = FailurePoint::new(FailurePointId(1), "Fetch::poll");
+///             if let Some(failed) = poll_inject(fp) {
+///                 return failed;
+///             }
+///         }
+///         Poll::Ready(Ok(7))
+///     }
+/// }
+///
+/// let mut cx = Context::from_waker(Waker::noop());
+/// assert_eq!(pin!(Fetch { started: false }).poll(&mut cx), Poll::Ready(Ok(7)));
+///
+/// let _guard = with_thread_config(FailureConfig::new().with_probability(1.0));
+/// assert!(matches!(pin!(Fetch { started: false }).poll(&mut cx), Poll::Ready(Err(_))));
+/// ```
+pub fn poll_inject<T, E: FallibleError>(fp: FailurePoint) -> Option<Poll<Result<T, E>>> {
+    should_simulate_failure(fp).then(|| Poll::Ready(Err(E::simulated_failure_for(fp))))
+}
+
 /// Handler trait for custom failure behavior.
 ///
 /// This should only be used if you need complete control over
@@ -100,14 +500,149 @@ pub trait FailureHandler {
     fn handle(&self, fp: FailurePoint) -> !;
 }
 
+/// Storage for the per-point call counters behind `trigger_every()`.
+///
+/// Install one with `FailureConfig::with_counter_store()` to keep counts outside the
+/// config, for example on disk so a restarted process picks up where the last one
+/// stopped. Each check calls `load()` and then `save()` with the incremented count;
+/// the pair isn't atomic, so stores shared between threads should expect
+/// interleaved calls.
+pub trait CounterStore: Send + Sync {
+    /// Get the number of calls seen so far for `id`, or 0 if it has none.
+    fn load(&self, id: FailurePointId) -> u64;
+
+    /// Record `count` calls for `id`.
+    fn save(&self, id: FailurePointId, count: u64);
+}
+
+/// A `CounterStore` that keeps counts in a text file, one `id count` pair per line.
+///
+/// The whole file is rewritten on every save, which is fine for test runs but not
+/// for hot paths. Write errors are ignored so a failing disk can't break the code
+/// under test.
+///
+/// # Example
+/// ```
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// let path = std::env::temp_dir().join(format!("fallibles-counts-{}", std::process::id()));
+/// # let _ = std::fs::remove_file(&path);
+/// let run = || {
+///     let store = FileCounterStore::open(&path).unwrap();
+///     let _guard = with_thread_config(FailureConfig::new().trigger_every(2).with_counter_store(store));
+///     should_simulate_failure(fp)
+/// };
+///
+/// // Each "restart" continues the count from the file
+/// assert_eq!([run(), run(), run()], [true, false, true]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub struct FileCounterStore {
+    path: std::path::PathBuf,
+    counts: std::sync::Mutex<alloc::collections::BTreeMap<u32, u64>>,
+}
+
+#[cfg(feature = "std")]
+impl FileCounterStore {
+    /// Open the store at `path`, loading its counts if the file exists.
+    pub fn open(path: impl Into<std::path::PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let mut counts = alloc::collections::BTreeMap::new();
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    let parsed = line
+                        .split_once(' ')
+                        .and_then(|(id, count)| Some((id.parse().ok()?, count.parse().ok()?)));
+                    let Some((id, count)) = parsed else {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            std::format!("malformed counter line: {line:?}"),
+                        ));
+                    };
+                    counts.insert(id, count);
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        Ok(Self {
+            path,
+            counts: std::sync::Mutex::new(counts),
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl CounterStore for FileCounterStore {
+    fn load(&self, id: FailurePointId) -> u64 {
+        let counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        counts.get(&id.0).copied().unwrap_or(0)
+    }
+
+    fn save(&self, id: FailurePointId, count: u64) {
+        use core::fmt::Write;
+
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        counts.insert(id.0, count);
+        let mut contents = alloc::string::String::new();
+        for (id, count) in counts.iter() {
+            let _ = writeln!(contents, "{id} {count}");
+        }
+        let _ = std::fs::write(&self.path, contents);
+    }
+}
+
 /// Unique identifier for a failure point.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FailurePointId(pub u32);
 
+impl FailurePointId {
+    /// Get the id `#[fallible]` assigns to a function named `name`.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::FailurePointId;
+    ///
+    /// assert_eq!(FailurePointId::from_name("load"), FailurePointId::from_name("load"));
+    /// assert_ne!(FailurePointId::from_name("load"), FailurePointId::from_name("save"));
+    /// ```
+    pub fn from_name(name: &str) -> Self {
+        FailurePointId(fxhash::hash32(name.as_bytes()))
+    }
+}
+
+/// Blast radius of a failure point.
+///
+/// Severities are ordered from least to most severe, so a config can limit
+/// injection to points at or below a given level with `max_severity()`.
+/// Points that don't declare a severity are `Medium`.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::Severity;
+///
+/// assert!(Severity::Low < Severity::Critical);
+/// assert_eq!(Severity::default(), Severity::Medium);
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
 /// Information about a specific failure point.
 ///
-/// Contains location metadata (file, line, column) and a unique identifier.
-/// This is passed to callbacks for observability and debugging.
+/// Contains location metadata (file, line, column), a unique identifier and
+/// the declared severity. This is passed to callbacks for observability and debugging.
 #[derive(Copy, Clone, Debug)]
 pub struct FailurePoint {
     pub id: FailurePointId,
@@ -115,6 +650,126 @@ pub struct FailurePoint {
     pub file: &'static str,
     pub line: u32,
     pub column: u32,
+    pub severity: Severity,
+}
+
+impl FailurePoint {
+    /// Describe the point `id` in `function`, located where this is called.
+    ///
+    /// The severity is `Medium`; use `with_severity()` for another one. Other
+    /// fields can be overridden with struct update syntax.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// assert_eq!((fp.file, fp.line), (file!(), line!() - 1));
+    /// assert_eq!(fp.severity, Severity::Medium);
+    ///
+    /// let elsewhere = FailurePoint { file: "src/users.rs", ..FailurePoint::new(FailurePointId(2), "load_user") };
+    /// assert_eq!(elsewhere.file, "src/users.rs");
+    /// ```
+    #[track_caller]
+    pub fn new(id: FailurePointId, function: &'static str) -> Self {
+        let location = core::panic::Location::caller();
+        FailurePoint {
+            id,
+            function,
+            file: location.file(),
+            line: location.line(),
+            column: location.column(),
+            severity: Severity::Medium,
+        }
+    }
+
+    /// Describe the point of the function or step named `name`, with the id
+    /// `#[fallible]` gives it, located where this is called.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let fp = FailurePoint::named("db_query");
+    /// assert_eq!(fp.id, FailurePointId::from_name("db_query"));
+    /// ```
+    #[track_caller]
+    pub fn named(name: &'static str) -> Self {
+        Self::new(FailurePointId::from_name(name), name)
+    }
+
+    /// Set the severity of this point.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let fp = FailurePoint::named("drop_table").with_severity(Severity::Critical);
+    /// assert_eq!(fp.severity, Severity::Critical);
+    /// ```
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+}
+
+/// Failure behavior declared inline with `#[fallible(...)]` attributes.
+///
+/// Replaces the active config's probability and trigger interval for one point.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InlinePolicy {
+    /// Raw probability threshold (`u32::MAX` is 100%, 0 when unset)
+    pub probability: u32,
+    /// Fail every nth call of this point (0 when unset)
+    pub trigger_every: u64,
+    /// Call index of the first `trigger_every` failure
+    pub trigger_offset: u64,
+    /// Only fail when the config names this point with `enable_point()` or
+    /// `enable_named()`; without a rate of its own the config's rate is used
+    pub opt_in: bool,
+    /// Hash of the call's arguments, rolled against the probability instead of
+    /// the call counter so the same arguments always get the same outcome; without
+    /// a rate of its own the config's rate is used
+    pub args_hash: Option<u64>,
+}
+
+impl InlinePolicy {
+    /// Whether this policy leaves the rate to the config.
+    fn defers_rate(&self) -> bool {
+        (self.opt_in || self.args_hash.is_some()) && self.probability == 0 && self.trigger_every == 0
+    }
+}
+
+/// How a check that passed every gate is decided.
+///
+/// Built from the trigger interval and probability that apply to the check, so
+/// "always fail" has one representation whichever builder produced it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TriggerMode {
+    /// No rate set, every check passes
+    Never,
+    /// 100% probability, every check fails without hashing
+    Always,
+    /// Fail every nth call, counted per point unless `global`
+    Every { n: u64, global: bool, offset: u64 },
+    /// Fail when the check's hash is below this raw threshold
+    Probability(u32),
+}
+
+impl TriggerMode {
+    fn new(trigger_every: u64, global: bool, offset: u64, probability: u32) -> Self {
+        match (trigger_every, probability) {
+            (0, 0) => TriggerMode::Never,
+            (0, u32::MAX) => TriggerMode::Always,
+            (0, probability) => TriggerMode::Probability(probability),
+            (n, _) => TriggerMode::Every { n, global, offset },
+        }
+    }
+
+    /// Whether this mode fails every check that reaches it.
+    fn always_fails(self) -> bool {
+        matches!(self, TriggerMode::Always | TriggerMode::Every { n: 1, .. })
+    }
 }
 
 pub struct PanicHandler;
@@ -131,25 +786,203 @@ impl FailureHandler for PanicHandler {
 static GLOBAL_HANDLER_DATA: AtomicUsize = AtomicUsize::new(0);
 static GLOBAL_HANDLER_VTABLE: AtomicUsize = AtomicUsize::new(0);
 static CONFIG_PTR: AtomicUsize = AtomicUsize::new(0);
+static DEFAULT_CONFIG_PTR: AtomicUsize = AtomicUsize::new(0);
+static INJECTION_DISABLED: AtomicBool = AtomicBool::new(false);
 
 #[cfg(feature = "std")]
 use std::cell::RefCell;
 
+#[cfg(feature = "std")]
+use std::cell::Cell;
+
 #[cfg(feature = "std")]
 std::thread_local! {
     static THREAD_CONFIG_PTR: RefCell<usize> = const { RefCell::new(0) };
+    static THREAD_INDEX: Cell<Option<u64>> = const { Cell::new(None) };
+    static THREAD_GUARD_DEPTH: Cell<usize> = const { Cell::new(0) };
+    static CURRENT_POINT: Cell<Option<FailurePoint>> = const { Cell::new(None) };
+    /// Variant picked by an `Outcome::ErrVariant` for the failure being built.
+    static REQUESTED_VARIANT: Cell<Option<&'static str>> = const { Cell::new(None) };
+    /// Per-thread check counter for `with_per_thread_seed`, as `(config id, count)`.
+    static THREAD_STREAM: Cell<(u64, u64)> = const { Cell::new((0, 0)) };
 }
 
-/// Callback function type for observability hooks.
-///
-/// Used with `on_check()` and `on_failure()` to monitor failures.
-pub type FailureCallback = Box<dyn Fn(FailurePoint) + Send + Sync>;
+#[cfg(feature = "std")]
+static NEXT_CONFIG_ID: AtomicU64 = AtomicU64::new(1);
+#[cfg(feature = "std")]
+static GLOBAL_GUARD_DEPTH: AtomicUsize = AtomicUsize::new(0);
+#[cfg(feature = "std")]
+static NEXT_THREAD_INDEX: AtomicU64 = AtomicU64::new(0);
 
-/// Predicate function type for conditional failure injection.
+/// Get the index of the current thread, used by `with_per_thread_seed`.
 ///
-/// Used with `when()` to dynamically control if a failure can occur.
+/// Threads are numbered in the order they first ask for an index, unless
+/// one was assigned with `set_thread_index()`.
+#[cfg(feature = "std")]
+pub fn thread_index() -> u64 {
+    THREAD_INDEX.with(|cell| match cell.get() {
+        Some(index) => index,
+        None => {
+            let index = NEXT_THREAD_INDEX.fetch_add(1, Ordering::Relaxed);
+            cell.set(Some(index));
+            index
+        }
+    })
+}
+
+/// Assign a stable index to the current thread.
+///
+/// Use this when threads are spawned in a nondeterministic order but each should
+/// always see the same failure stream under `with_per_thread_seed`.
+#[cfg(feature = "std")]
+pub fn set_thread_index(index: u64) {
+    THREAD_INDEX.with(|cell| cell.set(Some(index)));
+}
+
+/// Fixed-capacity, lock-free table of per-point counters.
+///
+/// Slots are claimed with a compare-exchange on first use and never released,
+/// so lookups only ever race with inserts. Each lookup probes at most `MAX_PROBE`
+/// slots, so a point that finds no free slot within that window isn't tracked and a
+/// full table doesn't turn every miss into a scan of the whole table.
+struct PointTable<const N: usize> {
+    slots: [PointSlot; N],
+}
+
+struct PointSlot {
+    key: AtomicU64,
+    count: AtomicU64,
+}
+
+const OCCUPIED: u64 = 1 << 32;
+
+/// Number of slots a `PointTable` lookup probes before giving up.
+const MAX_PROBE: usize = 16;
+
+impl<const N: usize> PointTable<N> {
+    const fn new() -> Self {
+        Self {
+            slots: [const {
+                PointSlot {
+                    key: AtomicU64::new(0),
+                    count: AtomicU64::new(0),
+                }
+            }; N],
+        }
+    }
+
+    fn slot(&self, id: FailurePointId, insert: bool) -> Option<&PointSlot> {
+        let key = id.0 as u64 | OCCUPIED;
+        let start = id.0 as usize % N;
+        for i in 0..N.min(MAX_PROBE) {
+            let slot = &self.slots[(start + i) % N];
+            let current = slot.key.load(Ordering::Acquire);
+            if current == key {
+                return Some(slot);
+            }
+            if current == 0 {
+                if !insert {
+                    return None;
+                }
+                match slot.key.compare_exchange(0, key, Ordering::AcqRel, Ordering::Acquire) {
+                    Ok(_) => return Some(slot),
+                    Err(actual) if actual == key => return Some(slot),
+                    Err(_) => {}
+                }
+            }
+        }
+        None
+    }
+
+    /// Increment the counter for `id`, returning the previous value.
+    ///
+    /// Returns `None` when `id` has no slot and none is free within its probe window.
+    fn increment(&self, id: FailurePointId) -> Option<u64> {
+        self.slot(id, true)
+            .map(|slot| slot.count.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Set the counter for `id`, claiming a slot if needed.
+    fn set(&self, id: FailurePointId, count: u64) {
+        if let Some(slot) = self.slot(id, true) {
+            slot.count.store(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Get the counter for `id` without claiming a slot.
+    fn get(&self, id: FailurePointId) -> u64 {
+        self.slot(id, false)
+            .map_or(0, |slot| slot.count.load(Ordering::Relaxed))
+    }
+
+    /// Zero every counter, keeping the claimed slots.
+    fn reset(&self) {
+        for slot in &self.slots {
+            slot.count.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn entries(&self) -> Vec<(FailurePointId, u64)> {
+        self.slots
+            .iter()
+            .filter_map(|slot| {
+                let key = slot.key.load(Ordering::Acquire);
+                (key != 0).then(|| (FailurePointId(key as u32), slot.count.load(Ordering::Relaxed)))
+            })
+            .collect()
+    }
+}
+
+/// Number of distinct failure points tracked by the global call registry.
+///
+/// Points that find no free slot, which mostly happens once the registry is close
+/// to full, aren't counted.
+pub const GLOBAL_REGISTRY_CAPACITY: usize = 4096;
+
+static CALL_COUNTS: PointTable<GLOBAL_REGISTRY_CAPACITY> = PointTable::new();
+
+/// Number of distinct failure points a single config counts `trigger_every()` for.
+///
+/// Points that find no free slot fall back to the config's shared counter.
+pub const CONFIG_POINT_CAPACITY: usize = 256;
+
+/// Callback function type for observability hooks.
+///
+/// Used with `on_check()` and `on_failure()` to monitor failures.
+pub type FailureCallback = Box<dyn Fn(FailurePoint) + Send + Sync>;
+
+/// Callback function type for observing every kind of injected fault.
+///
+/// Used with `on_failure_kind()`.
+pub type FailureKindCallback = Box<dyn Fn(FailurePoint, FailureKind) + Send + Sync>;
+
+/// Callback function type for observing injected errors.
+///
+/// Used with `on_failure_value()`; receives the error about to be returned.
+pub type FailureValueCallback = Box<dyn Fn(FailurePoint, &dyn core::fmt::Debug) + Send + Sync>;
+
+/// Predicate function type for conditional failure injection.
+///
+/// Used with `when()` to dynamically control if a failure can occur.
 pub type FailurePredicate = Box<dyn Fn() -> bool + Send + Sync>;
 
+/// Point-aware predicate function type for conditional failure injection.
+///
+/// Used with `when_point()` to decide per failure point if a failure can occur.
+pub type FailurePointPredicate = Box<dyn Fn(FailurePoint) -> bool + Send + Sync>;
+
+/// Clock function type for time-based failure injection.
+///
+/// Used with `with_clock()` to replace the real clock in `mtbf()` tests. Returns the
+/// time elapsed since an arbitrary fixed start.
+#[cfg(feature = "std")]
+pub type FailureClock = Box<dyn Fn() -> Duration + Send + Sync>;
+
+/// Veto function type for cancelling triggered failures.
+///
+/// Used with `failure_filter()` to suppress a failure after it was decided.
+pub type FailureFilter = Box<dyn Fn(FailurePoint) -> bool + Send + Sync>;
+
 /// Statistics about failure behavior.
 ///
 /// Tracks how many times failure points were checked and how many failures were triggered.
@@ -161,6 +994,8 @@ pub struct FailureStats {
     pub total_failures: u64,
     /// Number of checks that would have failed but were blocked by limits
     pub limited_failures: u64,
+    /// Number of checks that would have failed but were vetoed by `failure_filter()`
+    pub suppressed_failures: u64,
     /// Total latency injected in nanoseconds
     #[cfg(feature = "std")]
     pub total_latency_ns: u64,
@@ -168,6 +1003,24 @@ pub struct FailureStats {
 
 impl FailureStats {
     /// Get the failure rate as a percentage (0.0 to 100.0).
+    ///
+    /// Returns 0.0 when nothing was checked yet.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let config = FailureConfig::new().trigger_every(4);
+    /// assert_eq!(config.stats().failure_rate(), 0.0);
+    ///
+    /// let _guard = with_thread_config(config);
+    /// for _ in 0..8 {
+    ///     should_simulate_failure(fp);
+    /// }
+    /// assert_eq!(get_failure_stats().unwrap().failure_rate(), 25.0);
+    /// ```
     pub fn failure_rate(&self) -> f64 {
         if self.total_checks == 0 {
             0.0
@@ -176,11 +1029,41 @@ impl FailureStats {
         }
     }
 
+    /// Add `other`'s counts to these.
+    #[cfg(feature = "std")]
+    fn add(&mut self, other: &FailureStats) {
+        self.total_checks += other.total_checks;
+        self.total_failures += other.total_failures;
+        self.limited_failures += other.limited_failures;
+        self.suppressed_failures += other.suppressed_failures;
+        self.total_latency_ns += other.total_latency_ns;
+    }
+
     /// Get the success rate as a percentage (0.0 to 100.0).
     pub fn success_rate(&self) -> f64 {
         100.0 - self.failure_rate()
     }
 
+    /// Get the number of checks that didn't inject a failure.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// assert_eq!(FailureConfig::new().stats().checks_without_failure(), 0);
+    ///
+    /// let _guard = with_thread_config(FailureConfig::new().trigger_every(3));
+    /// for _ in 0..6 {
+    ///     should_simulate_failure(fp);
+    /// }
+    /// assert_eq!(get_failure_stats().unwrap().checks_without_failure(), 4);
+    /// ```
+    pub fn checks_without_failure(&self) -> u64 {
+        self.total_checks.saturating_sub(self.total_failures)
+    }
+
     /// Print statistics report to stdout.
     ///
     /// Uses ANSI colors: green for successes, red for failures, yellow for limited.
@@ -204,7 +1087,7 @@ impl FailureStats {
         println!(
             "  successes:        {}{}{} ({:.1}%)",
             GREEN,
-            self.total_checks.saturating_sub(self.total_failures),
+            self.checks_without_failure(),
             RESET,
             self.success_rate()
         );
@@ -214,6 +1097,12 @@ impl FailureStats {
                 YELLOW, self.limited_failures, RESET
             );
         }
+        if self.suppressed_failures > 0 {
+            println!(
+                "  suppressed:       {}{}{} (vetoed by failure_filter)",
+                YELLOW, self.suppressed_failures, RESET
+            );
+        }
         if self.total_latency_ns > 0 {
             let latency_ms = self.total_latency_ns as f64 / 1_000_000.0;
             println!(
@@ -231,6 +1120,236 @@ impl FailureStats {
     }
 }
 
+/// Record of one injected failure, for logs and event pipelines.
+///
+/// With the `serde` feature enabled, events implement `Serialize`. The `json` feature
+/// adds `FailureConfig::log_json()`, which writes one event per line.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FailureEvent {
+    /// Id of the failure point that failed
+    pub id: FailurePointId,
+    /// Function containing the failure point
+    pub function: &'static str,
+    /// Source file of the failure point
+    pub file: &'static str,
+    /// Line of the failure point
+    pub line: u32,
+    /// Column of the failure point
+    pub column: u32,
+    /// Declared severity of the failure point
+    pub severity: Severity,
+    /// Time of the failure in nanoseconds since the Unix epoch
+    pub timestamp_ns: u64,
+    /// Name of the failing thread, or its id if it has no name
+    pub thread: alloc::string::String,
+}
+
+#[cfg(feature = "std")]
+impl FailureEvent {
+    /// Describe a failure at `fp` happening now, on the current thread.
+    pub fn now(fp: FailurePoint) -> Self {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let thread = std::thread::current();
+        Self {
+            id: fp.id,
+            function: fp.function,
+            file: fp.file,
+            line: fp.line,
+            column: fp.column,
+            severity: fp.severity,
+            timestamp_ns: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0),
+            thread: match thread.name() {
+                Some(name) => name.into(),
+                None => std::format!("{:?}", thread.id()),
+            },
+        }
+    }
+}
+
+/// Plain-data copy of a `FailureConfig` for reproducing a run.
+///
+/// Captures the seed, probability, enabled points, limits and the current counters,
+/// so restoring it with `FailureConfig::from_snapshot()` continues the exact same
+/// failure sequence. Callbacks and predicates can't be captured and are dropped.
+///
+/// With the `serde` feature enabled, snapshots implement `Serialize` and `Deserialize`
+/// so they can be printed from CI and pasted back into a local run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FailureSnapshot {
+    /// Failure points that were explicitly enabled
+    pub enabled_points: Vec<FailurePointId>,
+    /// Raw probability threshold (`u32::MAX` is 100%)
+    pub probability: u32,
+    /// Raw probability thresholds from `with_probability_for_file()`, by path substring
+    pub file_probabilities: Vec<(alloc::string::String, u32)>,
+    /// Raw probability thresholds from `with_point_probability()`, sorted by id
+    pub point_probabilities: Vec<(FailurePointId, u32)>,
+    /// Checks per halving of the probability from `with_probability_decay()` (0 when disabled)
+    pub half_life: u64,
+    /// Deterministic trigger interval (0 when disabled)
+    pub trigger_every: u64,
+    /// Whether `trigger_every` counts all points together
+    pub trigger_every_global: bool,
+    /// Call index of the first `trigger_every` failure
+    pub trigger_offset: u64,
+    /// Raw `with_jitter()` threshold (`u32::MAX` is ±100%)
+    pub jitter: u32,
+    /// Whether the config's probability and interval win over inline attributes
+    pub override_inline: bool,
+    /// Seed for reproducible randomness (0 means unseeded unless `deterministic` is set)
+    pub seed: u64,
+    /// Whether the config came from `deterministic()`, so seed 0 is used as-is
+    pub deterministic: bool,
+    /// Whether each thread gets its own failure stream from `with_per_thread_seed()`
+    #[cfg(feature = "std")]
+    pub per_thread_seed: bool,
+    /// Whether the config is installed in release builds too
+    pub force_in_release: bool,
+    /// Maximum number of failures (0 when unlimited)
+    pub max_failures: u64,
+    /// Highest severity eligible for injection
+    pub max_severity: Severity,
+    /// Minimum injected latency in nanoseconds
+    #[cfg(feature = "std")]
+    pub latency_min_ns: u64,
+    /// Maximum injected latency in nanoseconds
+    #[cfg(feature = "std")]
+    pub latency_max_ns: u64,
+    /// Median of the `latency_profile()` in nanoseconds (0 when unset)
+    #[cfg(feature = "std")]
+    pub latency_p50_ns: u64,
+    /// 99th percentile of the `latency_profile()` in nanoseconds (0 when unset)
+    #[cfg(feature = "std")]
+    pub latency_p99_ns: u64,
+    /// Mean time between failures in nanoseconds (0 when disabled)
+    #[cfg(feature = "std")]
+    pub mtbf_ns: u64,
+    /// Start and end of the `with_time_window()` in nanoseconds since the Unix epoch
+    #[cfg(feature = "std")]
+    pub time_window_ns: Option<(u64, u64)>,
+    /// Number of checks that reached the trigger logic so far
+    pub counter: u64,
+    /// Number of checks performed so far, including those gated by predicates
+    pub checks_seen: u64,
+    /// Number of failures triggered so far
+    pub failures_triggered: u64,
+    /// Number of failures blocked by `max_failures` so far
+    pub limited_failures: u64,
+    /// Per-point call counts used by `trigger_every`
+    pub point_counts: Vec<(FailurePointId, u64)>,
+}
+
+/// Ordered sequence of failure phases for scripting a test run.
+///
+/// Each phase applies its own `FailureConfig` for a fixed number of checks, then hands
+/// over to the next one. Once every phase has run, no more failures are injected.
+/// Install a scenario with `FailureConfig::from_scenario()`.
+///
+/// Only the trigger settings of a phase are used (probability, trigger interval,
+/// enabled points, severity and predicate); callbacks, limits and latency come from
+/// the config the scenario is installed in.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::*;
+///
+/// // 10 clean calls, 30% failures for 50 calls, a burst of 5 failures, then recovery
+/// let scenario = FailureScenario::new()
+///     .phase(10, FailureConfig::new())
+///     .phase(50, FailureConfig::new().with_probability(0.3))
+///     .phase(5, FailureConfig::enable_all());
+/// assert_eq!(scenario.total_calls(), 65);
+///
+/// let config = FailureConfig::from_scenario(scenario).with_seed(42);
+/// ```
+pub struct FailureScenario {
+    phases: Vec<ScenarioPhase>,
+}
+
+struct ScenarioPhase {
+    calls: u64,
+    config: FailureConfig,
+}
+
+impl FailureScenario {
+    /// Create an empty scenario that never fails.
+    pub fn new() -> Self {
+        Self { phases: Vec::new() }
+    }
+
+    /// Append a phase that applies `config` for the next `calls` checks.
+    pub fn phase(mut self, calls: u64, config: FailureConfig) -> Self {
+        self.phases.push(ScenarioPhase { calls, config });
+        self
+    }
+
+    /// Get the number of checks covered by all phases together.
+    pub fn total_calls(&self) -> u64 {
+        self.phases.iter().map(|phase| phase.calls).sum()
+    }
+
+    /// Find the phase that owns check number `index`.
+    fn phase_at(&self, mut index: u64) -> Option<&FailureConfig> {
+        for phase in &self.phases {
+            if index < phase.calls {
+                return Some(&phase.config);
+            }
+            index -= phase.calls;
+        }
+        None
+    }
+
+    /// Copy every phase with `FailureConfig::clone_settings()`.
+    fn clone_settings(&self) -> Self {
+        Self {
+            phases: self
+                .phases
+                .iter()
+                .map(|phase| ScenarioPhase {
+                    calls: phase.calls,
+                    config: phase.config.clone_settings(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Default for FailureScenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What one call of a point does under `FailureConfig::sequence()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The check passes and the function runs normally.
+    Ok,
+    /// The check fails with the error type's usual `simulated_failure()`.
+    Err,
+    /// The check fails with the named variant of a `#[derive(FallibleError)]` enum.
+    ///
+    /// Types that don't know the name fall back to their usual error.
+    ErrVariant(&'static str),
+}
+
+/// What an injected fault did to a call, as seen by `on_failure_kind()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FailureKind {
+    /// The check failed, so the call returns an error (or a poisoned value).
+    Error,
+    /// The call was held up by injected latency before its check.
+    Delay(core::time::Duration),
+}
+
 /// Configuration for failure injection behavior.
 ///
 /// Controls when and how failures are triggered. It supports probability-based,
@@ -252,26 +1371,112 @@ impl FailureStats {
 ///     .with_probability(1.0)
 ///     .when(|| std::env::var("CHAOS_MODE").is_ok());
 /// ```
+///
+/// Configs are `Send + Sync`, so one installed with `configure_failures()` can be
+/// checked from many threads at once:
+///
+/// ```rust,standalone_crate
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+/// use std::thread;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// let _guard = with_config(FailureConfig::new().trigger_every_global(4));
+///
+/// let handles: Vec<_> = (0..8)
+///     .map(|_| thread::spawn(move || (0..1000).filter(|_| should_simulate_failure(fp)).count()))
+///     .collect();
+/// let failures: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+///
+/// let stats = get_failure_stats().unwrap();
+/// assert_eq!(stats.total_checks, 8000);
+/// assert_eq!(stats.total_failures, 2000);
+/// assert_eq!(failures, 2000);
+/// ```
 pub struct FailureConfig {
     enabled_points: Vec<FailurePointId>,
     probability: u32,
+    probability_range: Option<(f64, f64)>,
+    probability_handle: Option<Arc<AtomicU32>>,
+    file_probabilities: Vec<(alloc::string::String, u32)>,
+    point_probabilities: Vec<(FailurePointId, u32)>,
+    half_life: u64,
     counter: AtomicU64,
+    checks_seen: AtomicU64,
     trigger_every: u64,
+    trigger_every_global: bool,
+    trigger_offset: u64,
+    jitter: u32,
+    override_inline: bool,
+    point_counts: Box<PointTable<CONFIG_POINT_CAPACITY>>,
+    counter_store: Option<Box<dyn CounterStore>>,
+    arm_after: Vec<(FailurePointId, FailurePointId)>,
+    passed_points: Option<Box<PointTable<CONFIG_POINT_CAPACITY>>>,
+    sequences: Vec<(FailurePointId, Vec<Outcome>)>,
+    sequence_counts: Option<Box<PointTable<CONFIG_POINT_CAPACITY>>>,
     on_check: Option<FailureCallback>,
     on_failure: Option<FailureCallback>,
+    on_nth_failure: Vec<(u64, FailureCallback)>,
+    on_failure_kind: Option<FailureKindCallback>,
+    on_failure_value: Option<FailureValueCallback>,
+    failure_filter: Option<FailureFilter>,
     failures_triggered: AtomicU64,
     seed: u64,
     predicate: Option<FailurePredicate>,
+    point_predicate: Option<FailurePointPredicate>,
     #[cfg(feature = "std")]
     latency_min_ns: u64,
     #[cfg(feature = "std")]
     latency_max_ns: u64,
+    #[cfg(feature = "std")]
+    latency_p50_ns: u64,
+    #[cfg(feature = "std")]
+    latency_p99_ns: u64,
+    #[cfg(feature = "std")]
+    latency_samples: AtomicU64,
     max_failures: u64,
     limited_failures: AtomicU64,
+    suppressed_failures: AtomicU64,
     #[cfg(feature = "std")]
     total_latency_ns: AtomicU64,
+    max_severity: Severity,
+    #[cfg(feature = "std")]
+    recorded: Option<std::sync::Mutex<Vec<bool>>>,
+    #[cfg(feature = "std")]
+    gaps: Option<std::sync::Mutex<GapHistogram>>,
+    replay: Option<Vec<bool>>,
+    force_in_release: bool,
+    strict: bool,
+    deterministic: bool,
+    scenario: Option<FailureScenario>,
+    #[cfg(feature = "std")]
+    id: u64,
+    #[cfg(feature = "std")]
+    per_thread_seed: bool,
+    #[cfg(feature = "std")]
+    one_shot: bool,
+    #[cfg(feature = "std")]
+    mtbf_ns: u64,
+    #[cfg(feature = "std")]
+    last_failure_ns: AtomicU64,
+    #[cfg(feature = "std")]
+    time_window: Option<(std::time::SystemTime, std::time::SystemTime)>,
+    #[cfg(feature = "std")]
+    clock: Option<FailureClock>,
+    #[cfg(feature = "std")]
+    started: std::time::Instant,
+    #[cfg(feature = "json")]
+    json_log: Option<std::sync::Mutex<Box<dyn std::io::Write + Send>>>,
 }
 
+// The active config is shared between threads through raw pointers, so a field that
+// isn't `Send + Sync` must fail to compile rather than cause a data race.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<FailureConfig>();
+    assert_send_sync::<FailureScenario>();
+};
+
 impl FailureConfig {
     /// Create a new failure configuration with no failures enabled.
     ///
@@ -280,21 +1485,77 @@ impl FailureConfig {
         Self {
             enabled_points: Vec::new(),
             probability: 0,
+            probability_range: None,
+            probability_handle: None,
+            file_probabilities: Vec::new(),
+            point_probabilities: Vec::new(),
+            half_life: 0,
             counter: AtomicU64::new(0),
+            checks_seen: AtomicU64::new(0),
             trigger_every: 0,
+            trigger_every_global: false,
+            trigger_offset: 0,
+            jitter: 0,
+            override_inline: false,
+            point_counts: Box::new(PointTable::new()),
+            counter_store: None,
+            arm_after: Vec::new(),
+            passed_points: None,
+            sequences: Vec::new(),
+            sequence_counts: None,
             on_check: None,
             on_failure: None,
+            on_nth_failure: Vec::new(),
+            on_failure_kind: None,
+            on_failure_value: None,
+            failure_filter: None,
             failures_triggered: AtomicU64::new(0),
             seed: 0,
             predicate: None,
+            point_predicate: None,
             #[cfg(feature = "std")]
             latency_min_ns: 0,
             #[cfg(feature = "std")]
             latency_max_ns: 0,
+            #[cfg(feature = "std")]
+            latency_p50_ns: 0,
+            #[cfg(feature = "std")]
+            latency_p99_ns: 0,
+            #[cfg(feature = "std")]
+            latency_samples: AtomicU64::new(0),
             max_failures: 0,
             limited_failures: AtomicU64::new(0),
+            suppressed_failures: AtomicU64::new(0),
             #[cfg(feature = "std")]
             total_latency_ns: AtomicU64::new(0),
+            max_severity: Severity::Critical,
+            #[cfg(feature = "std")]
+            recorded: None,
+            #[cfg(feature = "std")]
+            gaps: None,
+            replay: None,
+            force_in_release: false,
+            strict: false,
+            deterministic: false,
+            scenario: None,
+            #[cfg(feature = "std")]
+            id: NEXT_CONFIG_ID.fetch_add(1, Ordering::Relaxed),
+            #[cfg(feature = "std")]
+            per_thread_seed: false,
+            #[cfg(feature = "std")]
+            one_shot: false,
+            #[cfg(feature = "std")]
+            mtbf_ns: 0,
+            #[cfg(feature = "std")]
+            last_failure_ns: AtomicU64::new(0),
+            #[cfg(feature = "std")]
+            time_window: None,
+            #[cfg(feature = "std")]
+            clock: None,
+            #[cfg(feature = "std")]
+            started: std::time::Instant::now(),
+            #[cfg(feature = "json")]
+            json_log: None,
         }
     }
 
@@ -304,6 +1565,7 @@ impl FailureConfig {
     ///
     /// # Example
     /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
     /// let config = FailureConfig::chaos_monkey();
     /// ```
     pub fn chaos_monkey() -> Self {
@@ -316,6 +1578,7 @@ impl FailureConfig {
     ///
     /// # Example
     /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
     /// // 30% of requests fail
     /// let config = FailureConfig::degraded_service(0.3);
     /// ```
@@ -329,6 +1592,7 @@ impl FailureConfig {
     ///
     /// # Example
     /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
     /// // Fail every 5th call
     /// let config = FailureConfig::circuit_breaker(5);
     /// ```
@@ -336,295 +1600,2594 @@ impl FailureConfig {
         Self::new().trigger_every(failure_threshold)
     }
 
-    /// Enable all failure points with 100% failure rate.
+    /// API Throttling policy: bursts of failures with recovery in between.
     ///
-    /// Useful for testing that all failure points are correctly handled.
-    pub fn enable_all() -> Self {
-        Self {
-            enabled_points: Vec::new(),
-            probability: u32::MAX,
-            counter: AtomicU64::new(0),
-            trigger_every: 0,
-            on_check: None,
-            on_failure: None,
-            failures_triggered: AtomicU64::new(0),
-            seed: 0,
-            predicate: None,
-            #[cfg(feature = "std")]
-            latency_min_ns: 0,
-            #[cfg(feature = "std")]
-            latency_max_ns: 0,
-            max_failures: 0,
-            limited_failures: AtomicU64::new(0),
-            #[cfg(feature = "std")]
-            total_latency_ns: AtomicU64::new(0),
-        }
-    }
-
-    /// Enable failures for a specific failure point ID.
+    /// Models rate limiting like HTTP 429 responses: 40 checks succeed, the next 10
+    /// fail, and the cycle repeats. The bursts are driven by a `when()` predicate, so
+    /// adding another `when()` replaces them.
     ///
-    /// When using `enable_point()`, only the specified points will fail.
-    pub fn enable_point(mut self, id: FailurePointId) -> Self {
-        self.enabled_points.push(id);
-        self
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(FailureConfig::aws_throttling());
+    /// let calls: Vec<bool> = (0..100).map(|_| should_simulate_failure(fp)).collect();
+    ///
+    /// for cycle in calls.chunks(50) {
+    ///     assert!(cycle[..40].iter().all(|failed| !failed));
+    ///     assert!(cycle[40..].iter().all(|failed| *failed));
+    /// }
+    /// ```
+    pub fn aws_throttling() -> Self {
+        let calls = AtomicU64::new(0);
+        Self::enable_all().when(move || calls.fetch_add(1, Ordering::Relaxed) % 50 >= 40)
     }
 
-    /// Set probability of failure (0.0 to 1.0).
+    /// Network Partition policy: healthy, then cut off completely, then healthy again.
     ///
-    /// Each failure point check will fail with this probability.
+    /// The first `healthy_calls` checks succeed, the next `partition_calls` all fail,
+    /// and every check after that succeeds.
     ///
     /// # Example
     /// ```
-    /// // 25% failure rate
-    /// let config = FailureConfig::new().with_probability(0.25);
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(FailureConfig::network_partition(5, 10));
+    /// let calls: Vec<bool> = (0..100).map(|_| should_simulate_failure(fp)).collect();
+    ///
+    /// assert!(calls[..5].iter().all(|failed| !failed));
+    /// assert!(calls[5..15].iter().all(|failed| *failed));
+    /// assert!(calls[15..].iter().all(|failed| !failed));
     /// ```
-    pub fn with_probability(mut self, prob: f64) -> Self {
-        self.probability = (prob * u32::MAX as f64) as u32;
-        self
+    pub fn network_partition(healthy_calls: u64, partition_calls: u64) -> Self {
+        Self::from_scenario(
+            FailureScenario::new()
+                .phase(healthy_calls, Self::new())
+                .phase(partition_calls, Self::enable_all()),
+        )
     }
 
-    /// Fail every nth call deterministically.
+    /// Slow Disk policy: latency on every check, but no errors.
     ///
-    /// Creates a predictable failure pattern for testing scenarios.
+    /// Checks take 2ms at the median and 20ms at the 99th percentile (see
+    /// `latency_profile()`), for testing timeouts and latency budgets.
     ///
     /// # Example
     /// ```
-    /// // Fail on calls 0, 3, 6, 9, ...
-    /// let config = FailureConfig::new().trigger_every(3);
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::time::Duration;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(FailureConfig::slow_disk());
+    ///
+    /// assert!((0..20).all(|_| !should_simulate_failure(fp)));
+    /// let stats = get_failure_stats().unwrap();
+    /// assert_eq!(stats.total_failures, 0);
+    /// assert!(stats.total_latency_ns >= Duration::from_millis(10).as_nanos() as u64);
     /// ```
-    pub fn trigger_every(mut self, n: u64) -> Self {
-        self.trigger_every = n;
-        self
+    #[cfg(feature = "std")]
+    pub fn slow_disk() -> Self {
+        Self::new().latency_profile(Duration::from_millis(2), Duration::from_millis(20))
     }
 
-    /// Set a seed for reproducible randomness.
+    /// Deterministic policy: seeded failures that are identical on every run.
+    ///
+    /// Sets the seed and probability, and never falls back to time-based entropy,
+    /// even for a seed of 0. The preset for reproducible CI suites.
     ///
     /// # Example
     /// ```
-    /// // Same seed always produces same failure pattern
-    /// let config = FailureConfig::new()
-    ///     .with_probability(0.3)
-    ///     .with_seed(12345);
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// // Two runs of the same suite
+    /// let run = |seed| {
+    ///     let _guard = with_thread_config(FailureConfig::deterministic(seed, 0.3));
+    ///     (0..1000).map(|_| should_simulate_failure(fp)).collect::<Vec<_>>()
+    /// };
+    ///
+    /// assert_eq!(run(42), run(42));
+    /// assert_eq!(run(0), run(0));
+    /// assert_ne!(run(42), run(43));
     /// ```
-    pub fn with_seed(mut self, seed: u64) -> Self {
-        self.seed = seed;
-        self
+    pub fn deterministic(seed: u64, probability: f64) -> Self {
+        Self {
+            deterministic: true,
+            ..Self::new()
+        }
+        .with_probability(probability)
+        .with_seed(seed)
     }
 
-    /// Set seed from `FALLIBLES_SEED` environment variable.
+    /// Run a `FailureScenario`, advancing one call per check.
     ///
-    /// If the environment variable is not set or invalid, uses default (0).
+    /// The scenario replaces this config's own probability, trigger interval and
+    /// `mtbf()`. Seeds, predicates, limits and callbacks can still be added with the
+    /// usual builder methods.
     ///
     /// # Example
-    /// ```bash
-    /// FALLIBLES_SEED=12345 cargo test
     /// ```
-    #[cfg(feature = "std")]
-    pub fn with_seed_from_env(mut self) -> Self {
-        if let Ok(seed_str) = std::env::var("FALLIBLES_SEED") {
-            if let Ok(seed) = seed_str.parse::<u64>() {
-                self.seed = seed;
-            }
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let scenario = FailureScenario::new()
+    ///     .phase(3, FailureConfig::new())
+    ///     .phase(4, FailureConfig::new().trigger_every(2))
+    ///     .phase(2, FailureConfig::enable_all());
+    /// let _guard = with_thread_config(FailureConfig::from_scenario(scenario));
+    ///
+    /// let calls: Vec<bool> = (0..11).map(|_| should_simulate_failure(fp)).collect();
+    /// assert_eq!(
+    ///     calls,
+    ///     [false, false, false, true, false, true, false, true, true, false, false]
+    /// );
+    /// ```
+    pub fn from_scenario(scenario: FailureScenario) -> Self {
+        Self {
+            scenario: Some(scenario),
+            ..Self::new()
         }
-        self
     }
 
-    /// Set a predicate that must return true for failures to occur.
+    /// Fail the first check, then never again.
     ///
-    /// Allows control over when failures are enabled based on runtime conditions.
+    /// Shorthand for `enable_all().max_failures(1)`.
     ///
     /// # Example
     /// ```
-    /// // Only fail when chaos mode is enabled
-    /// let config = FailureConfig::new()
-    ///     .with_probability(0.5)
-    ///     .when(|| std::env::var("CHAOS_MODE").is_ok());
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(FailureConfig::once());
+    /// let calls: Vec<bool> = (0..3).map(|_| should_simulate_failure(fp)).collect();
+    /// assert_eq!(calls, [true, false, false]);
     /// ```
-    pub fn when<F>(mut self, predicate: F) -> Self
-    where
-        F: Fn() -> bool + Send + Sync + 'static,
-    {
-        self.predicate = Some(Box::new(predicate));
+    pub fn once() -> Self {
+        Self::enable_all().max_failures(1)
+    }
+
+    /// Enable all failure points with 100% failure rate.
+    ///
+    /// Useful for testing that all failure points are correctly handled. A 100% rate
+    /// always fails without hashing, however it was set: `with_probability(1.0)`,
+    /// `with_probability_per_mille(1000)` and `trigger_every(1)` behave the same, and
+    /// `strict()` treats all of them as "must fail". A 0% rate never fails.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let point = |id| FailurePoint::new(FailurePointId(id), "db_query");
+    /// let failures = |config: FailureConfig| {
+    ///     let _guard = with_thread_config(config.strict(true));
+    ///     (0..1000).filter(|&id| should_simulate_failure(point(id))).count()
+    /// };
+    ///
+    /// assert_eq!(failures(FailureConfig::enable_all()), 1000);
+    /// assert_eq!(failures(FailureConfig::new().with_probability(1.0)), 1000);
+    /// assert_eq!(failures(FailureConfig::new().with_probability(1.0).with_seed(7)), 1000);
+    /// assert_eq!(failures(FailureConfig::new().with_probability_per_mille(1000)), 1000);
+    /// assert_eq!(failures(FailureConfig::new().trigger_every(1)), 1000);
+    /// assert_eq!(failures(FailureConfig::new().trigger_every_global(2)), 500);
+    /// assert_eq!(failures(FailureConfig::new().with_probability(0.0)), 0);
+    /// assert_eq!(failures(FailureConfig::new()), 0);
+    /// assert_eq!(failures(FailureConfig::once()), 1);
+    /// ```
+    pub fn enable_all() -> Self {
+        Self {
+            probability: u32::MAX,
+            ..Self::new()
+        }
+    }
+
+    /// Enable failures for a specific failure point ID.
+    ///
+    /// When using `enable_point()`, only the specified points will fail. Enabled points
+    /// are kept sorted, so each check is a binary search however many are enabled.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let point = |id| FailurePoint::new(FailurePointId(id), "db_query");
+    /// // Every odd id from 1 to 1999, enabled in reverse order
+    /// let config = (0..1000)
+    ///     .rev()
+    ///     .fold(FailureConfig::enable_all(), |config, i| config.enable_point(FailurePointId(i * 2 + 1)));
+    /// let _guard = with_thread_config(config);
+    ///
+    /// assert!((1..2000).step_by(2).all(|id| should_simulate_failure(point(id))));
+    /// assert!((0..2001).step_by(2).all(|id| !should_simulate_failure(point(id))));
+    /// ```
+    pub fn enable_point(mut self, id: FailurePointId) -> Self {
+        if let Err(index) = self.enabled_points.binary_search_by_key(&id.0, |point| point.0) {
+            self.enabled_points.insert(index, id);
+        }
         self
     }
 
-    /// Add artificial latency/delay to failure points.
+    /// Enable failures for the function or step named `name`.
     ///
-    /// Adds a delay (in the specified range) every time a failure point is checked,
-    /// even if no failure is triggered. Useful for simulating slow networks or I/O.
+    /// Uses the same id as `#[fallible]` and `fallible_step!`, so there's no hash to
+    /// compute by hand.
     ///
     /// # Example
     /// ```
-    /// use std::time::Duration;
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
     ///
-    /// // Add 10-50ms latency to all checks
-    /// let config = FailureConfig::new()
-    ///     .with_probability(0.2)
-    ///     .with_latency(Duration::from_millis(10), Duration::from_millis(50));
+    /// let point = |function: &'static str| FailurePoint::named(function);
     ///
-    /// // Fixed latency
-    /// let config = FailureConfig::new()
-    ///     .with_latency(Duration::from_millis(100), Duration::from_millis(100));
+    /// let _guard = with_thread_config(FailureConfig::enable_all().enable_named("database_query"));
+    /// assert!(should_simulate_failure(point("database_query")));
+    /// assert!(!should_simulate_failure(point("cache_lookup")));
     /// ```
-    #[cfg(feature = "std")]
-    pub fn with_latency(mut self, min: Duration, max: Duration) -> Self {
-        self.latency_min_ns = min.as_nanos() as u64;
-        self.latency_max_ns = max.as_nanos() as u64;
+    pub fn enable_named(self, name: &str) -> Self {
+        self.enable_point(FailurePointId::from_name(name))
+    }
+
+    /// Enable failures for every function or step in `names`.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// let config = FailureConfig::enable_all().enable_many(&["connect", "fetch"]);
+    /// ```
+    pub fn enable_many(self, names: &[&str]) -> Self {
+        names.iter().fold(self, |config, name| config.enable_named(name))
+    }
+
+    /// Keep `trigger` from failing until a check of `target` has passed.
+    ///
+    /// A check passes when it doesn't inject a failure. Until then `trigger` always
+    /// succeeds; afterwards it fails as the rest of the config decides. Useful for
+    /// ordering bugs, like a fetch that only breaks once a connection was made.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let point = |function: &'static str| FailurePoint::named(function);
+    /// let (connect, fetch) = (point("connect"), point("fetch"));
+    ///
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::enable_all()
+    ///         .enable_point(fetch.id)
+    ///         .arm_point_after(connect.id, fetch.id),
+    /// );
+    ///
+    /// assert!(!should_simulate_failure(fetch));
+    /// assert!(!should_simulate_failure(connect));
+    /// assert!(should_simulate_failure(fetch));
+    /// ```
+    pub fn arm_point_after(mut self, target: FailurePointId, trigger: FailurePointId) -> Self {
+        self.arm_after.push((target, trigger));
+        self.passed_points.get_or_insert_with(|| Box::new(PointTable::new()));
         self
     }
 
-    /// Limit the total number of failures that can be triggered.
+    /// Script the outcome of each call of `id`, starting over after the last one.
     ///
-    /// Once this limit is reached, no more failures will occur even if
-    /// probability or other conditions would trigger them.
+    /// Call `n` of the point plays `outcomes[n % outcomes.len()]`, ahead of the
+    /// rate, probability and scenario settings. Severity, predicates, enabled
+    /// points and `arm_point_after()` still gate the check, and a gated call doesn't
+    /// use up an entry. An `Outcome::ErrVariant` is read by derived `FallibleError`
+    /// enums through `requested_variant()`. A later `sequence()` for the same point
+    /// replaces the earlier one.
+    ///
+    /// # Panics
+    /// Panics if `outcomes` is empty.
     ///
     /// # Example
     /// ```
-    /// // Allow at most 5 failures, then stop
-    /// let config = FailureConfig::new()
-    ///     .with_probability(0.5)
-    ///     .max_failures(5);
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new().sequence(fp.id, vec![Outcome::Ok, Outcome::Ok, Outcome::Err]),
+    /// );
+    ///
+    /// let decisions: Vec<bool> = (0..6).map(|_| should_simulate_failure(fp)).collect();
+    /// assert_eq!(decisions, [false, false, true, false, false, true]);
     /// ```
-    pub fn max_failures(mut self, max: u64) -> Self {
-        self.max_failures = max;
+    pub fn sequence(mut self, id: FailurePointId, outcomes: Vec<Outcome>) -> Self {
+        assert!(!outcomes.is_empty(), "sequence needs at least one outcome");
+        self.sequences.retain(|(point, _)| *point != id);
+        self.sequences.push((id, outcomes));
+        self.sequence_counts.get_or_insert_with(|| Box::new(PointTable::new()));
         self
     }
 
-    /// Register a callback that's called every time a failure point is checked.
-    /// The callback receives information about the failure point being checked.
+    /// Take the next scripted `sequence()` outcome for `id`, if it has one.
+    fn next_outcome(&self, id: FailurePointId) -> Option<Outcome> {
+        let counts = self.sequence_counts.as_ref()?;
+        let (_, outcomes) = self.sequences.iter().find(|(point, _)| *point == id)?;
+        let index = counts.increment(id)?;
+        Some(outcomes[(index % outcomes.len() as u64) as usize])
+    }
+
+    /// Check whether every `arm_point_after()` gate on `id` is open.
+    fn point_armed(&self, id: FailurePointId) -> bool {
+        let Some(passed) = &self.passed_points else {
+            return true;
+        };
+        self.arm_after
+            .iter()
+            .filter(|(_, trigger)| *trigger == id)
+            .all(|(target, _)| passed.get(*target) > 0)
+    }
+
+    /// Remember that a check of `id` passed, if some gate waits on it.
+    fn note_passed(&self, id: FailurePointId) {
+        if let Some(passed) = &self.passed_points
+            && self.arm_after.iter().any(|(target, _)| *target == id)
+        {
+            passed.increment(id);
+        }
+    }
+
+    /// Check whether `id` passes the `enable_point()` filter.
+    fn point_enabled(&self, id: FailurePointId) -> bool {
+        self.enabled_points.is_empty()
+            || self.enabled_points.binary_search_by_key(&id.0, |point| point.0).is_ok()
+    }
+
+    /// Set probability of failure (0.0 to 1.0).
+    ///
+    /// Each failure point check will fail with this probability.
     ///
     /// # Example
     /// ```
-    /// let config = FailureConfig::new()
-    ///     .with_probability(0.3)
-    ///     .on_check(|fp| println!("Checking: {}:{}", fp.file, fp.line));
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// // 25% failure rate
+    /// let config = FailureConfig::new().with_probability(0.25);
     /// ```
-    pub fn on_check<F>(mut self, callback: F) -> Self
-    where
-        F: Fn(FailurePoint) + Send + Sync + 'static,
-    {
-        self.on_check = Some(Box::new(callback));
+    pub fn with_probability(mut self, prob: f64) -> Self {
+        self.probability_range = None;
+        self.set_probability(probability_threshold(prob));
         self
     }
 
-    /// Register a callback that's called when a failure is actually triggered.
+    /// Set probability of success (0.0 to 1.0), failing the remaining checks, i.e. `with_probability(1.0 - prob)`.
     ///
-    /// Useful for logging, metrics, or coordinating failures across multiple points.
+    /// The complement of `with_probability()`, for tests that exercise the rare
+    /// happy path under mostly failing conditions.
     ///
     /// # Example
     /// ```
-    /// let config = FailureConfig::new()
-    ///     .with_probability(0.3)
-    ///     .on_failure(|fp| eprintln!("FAILURE at {}:{}", fp.file, fp.line));
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// {
+    ///     let _guard = with_thread_config(FailureConfig::new().with_success_probability(0.0));
+    ///     assert!((0..1000).all(|_| should_simulate_failure(fp)));
+    /// }
+    ///
+    /// let _guard = with_thread_config(FailureConfig::new().with_success_probability(1.0));
+    /// assert!((0..1000).all(|_| !should_simulate_failure(fp)));
     /// ```
-    pub fn on_failure<F>(mut self, callback: F) -> Self
-    where
-        F: Fn(FailurePoint) + Send + Sync + 'static,
-    {
-        self.on_failure = Some(Box::new(callback));
+    pub fn with_success_probability(self, prob: f64) -> Self {
+        self.with_probability(1.0 - prob)
+    }
+
+    /// Use probability `prob` for points whose source file path contains `substr`.
+    ///
+    /// Replaces the config's probability for matching points, so a module like
+    /// `src/storage/` can fail more or less often than the rest of the code.
+    /// `trigger_every()` and inline attributes still take precedence the same way
+    /// they do over `with_probability()`. When several substrings match, the one
+    /// added first wins; adding the same substring again replaces its probability.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let point = |function: &'static str, file: &'static str| FailurePoint { file, line: 1, column: 1, ..FailurePoint::named(function) };
+    /// let write_block = point("write_block", "src/storage/block.rs");
+    /// let render = point("render", "src/ui/view.rs");
+    ///
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new().with_probability_for_file("src/storage/", 1.0),
+    /// );
+    ///
+    /// assert!((0..100).all(|_| should_simulate_failure(write_block)));
+    /// assert!((0..100).all(|_| !should_simulate_failure(render)));
+    /// ```
+    pub fn with_probability_for_file(mut self, substr: &str, prob: f64) -> Self {
+        let threshold = probability_threshold(prob);
+        match self.file_probabilities.iter_mut().find(|(file, _)| file == substr) {
+            Some(entry) => entry.1 = threshold,
+            None => self.file_probabilities.push((substr.into(), threshold)),
+        }
         self
     }
 
-    /// Get statistics about failure injection behavior.
+    /// Use probability `prob` for the point with id `id`.
     ///
-    /// Returns total checks and total failures triggered.
+    /// Takes precedence over `with_probability_for_file()` and the config's own
+    /// probability for that point. Inline attributes and `trigger_every()` still take
+    /// precedence the same way they do over `with_probability()`. Setting the same
+    /// point again replaces its probability.
     ///
     /// # Example
     /// ```
-    /// let stats = config.stats();
-    /// println!("Failure rate: {}/{}", stats.total_failures, stats.total_checks);
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new().with_probability_for_file(file!(), 0.0).with_point_probability(fp.id, 1.0),
+    /// );
+    /// assert!(should_simulate_failure(fp));
     /// ```
-    pub fn stats(&self) -> FailureStats {
-        FailureStats {
-            total_checks: self.counter.load(Ordering::Relaxed),
-            total_failures: self.failures_triggered.load(Ordering::Relaxed),
-            limited_failures: self.limited_failures.load(Ordering::Relaxed),
-            #[cfg(feature = "std")]
-            total_latency_ns: self.total_latency_ns.load(Ordering::Relaxed),
+    pub fn with_point_probability(mut self, id: FailurePointId, prob: f64) -> Self {
+        let threshold = probability_threshold(prob);
+        match self.point_probabilities.binary_search_by_key(&id.0, |(point, _)| point.0) {
+            Ok(index) => self.point_probabilities[index].1 = threshold,
+            Err(index) => self.point_probabilities.insert(index, (id, threshold)),
         }
+        self
     }
 
-    fn should_trigger(&self, fp_id: FailurePointId) -> bool {
-        if let Some(predicate) = &self.predicate
-            && !predicate() {
-                return false;
-            }
+    /// Use a probability of its own for every function or step named in `table`.
+    ///
+    /// Shorthand for `with_point_probability()` with `FailurePointId::from_name()`,
+    /// for setting up many points at different rates in one go.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let point = |function: &'static str| FailurePoint::named(function);
+    ///
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new()
+    ///         .with_probability_table(&[("connect", 0.1), ("query", 0.5), ("commit", 0.9)])
+    ///         .with_seed(7),
+    /// );
+    ///
+    /// for (function, expected) in [("connect", 0.1), ("query", 0.5), ("commit", 0.9)] {
+    ///     let fp = point(function);
+    ///     let failures = (0..10_000).filter(|_| should_simulate_failure(fp)).count();
+    ///     assert!((failures as f64 / 10_000.0 - expected).abs() < 0.03, "{function}: {failures}");
+    /// }
+    /// assert!((0..100).all(|_| !should_simulate_failure(point("rollback"))));
+    /// ```
+    pub fn with_probability_table(self, table: &[(&str, f64)]) -> Self {
+        table
+            .iter()
+            .fold(self, |config, (name, prob)| config.with_point_probability(FailurePointId::from_name(name), *prob))
+    }
 
-        if !self.enabled_points.is_empty() && !self.enabled_points.contains(&fp_id) {
-            return false;
+    /// Start failing with probability `start`, halving it every `half_life_calls` checks.
+    ///
+    /// Models a service that heals over time: the probability approaches zero as
+    /// checks accumulate, for testing that clients back off and stop retrying. A
+    /// `half_life_calls` of 0 keeps the probability constant.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new().with_probability_decay(0.8, 1000).with_seed(42),
+    /// );
+    ///
+    /// // Failures in successive windows of 1000 checks: about 800, 400, 200, 100
+    /// let windows: Vec<usize> = (0..4)
+    ///     .map(|_| (0..1000).filter(|_| should_simulate_failure(fp)).count())
+    ///     .collect();
+    /// for pair in windows.windows(2) {
+    ///     let ratio = pair[1] as f64 / pair[0] as f64;
+    ///     assert!((0.35..0.65).contains(&ratio), "{windows:?}");
+    /// }
+    /// ```
+    pub fn with_probability_decay(self, start: f64, half_life_calls: u64) -> Self {
+        Self {
+            half_life: half_life_calls,
+            ..self.with_probability(start)
         }
+    }
 
-        if self.trigger_every > 0 {
-            let count = self.counter.fetch_add(1, Ordering::Relaxed);
-            return count.is_multiple_of(self.trigger_every);
-        }
+    /// Pick the probability of failure uniformly from `[lo, hi]`.
+    ///
+    /// The pick is derived from the seed, so the same seed always chooses the same
+    /// probability, and is made again whenever the seed changes. Without a seed each
+    /// run picks a different value. The chosen probability shows up in `describe()`
+    /// and `snapshot()` for reproducing a run.
+    ///
+    /// # Panics
+    /// Panics unless `0.0 <= lo <= hi <= 1.0`.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::{FailureConfig, ratio_threshold};
+    /// let shard = |seed| FailureConfig::new().with_probability_range(0.1, 0.4).with_seed(seed);
+    ///
+    /// // Snapshots hold the raw threshold, where `u32::MAX` is 100%
+    /// let chosen = shard(7).snapshot().probability;
+    /// assert!((ratio_threshold(1, 10)..=ratio_threshold(2, 5)).contains(&chosen));
+    /// assert_eq!(shard(7).snapshot().probability, chosen);
+    /// assert_ne!(shard(8).snapshot().probability, chosen);
+    /// assert!(shard(7).describe().contains("drawn from 10.0%..=40.0%"));
+    /// ```
+    pub fn with_probability_range(mut self, lo: f64, hi: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&lo) && (lo..=1.0).contains(&hi),
+            "probability range must satisfy 0.0 <= lo <= hi <= 1.0"
+        );
+        self.probability_range = Some((lo, hi));
+        self.pick_probability();
+        self
+    }
 
-        if self.probability > 0 {
-            let counter = self.counter.fetch_add(1, Ordering::Relaxed);
-            let mut bytes = [0u8; 12];
-            bytes[0..4].copy_from_slice(&fp_id.0.to_le_bytes());
-            bytes[4..12].copy_from_slice(&counter.to_le_bytes());
+    /// Draw the probability from `probability_range`, if one is set.
+    fn pick_probability(&mut self) {
+        let Some((lo, hi)) = self.probability_range else {
+            return;
+        };
 
-            let hash1 = fxhash::hash32(&bytes);
-            let hash2 = fxhash::hash64(&bytes);
+        let mut x = self.seed;
+        #[cfg(feature = "std")]
+        if x == 0 && !self.deterministic {
+            use std::time::{SystemTime, UNIX_EPOCH};
+            x = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0);
+        }
+        x = x.wrapping_add(0x9e3779b97f4a7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
 
-            let mut combined = (hash1 as u64) ^ hash2;
+        let unit = (x >> 11) as f64 / (1u64 << 53) as f64;
+        let prob = lo + (hi - lo) * unit;
+        self.set_probability(probability_threshold(prob));
+    }
 
-            if self.seed != 0 {
-                combined ^= self.seed.wrapping_mul(0x517cc1b727220a95);
-            } else {
-                #[cfg(feature = "std")]
-                {
-                    use std::time::{SystemTime, UNIX_EPOCH};
-                    let nanos = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .map(|d| d.as_nanos() as u64)
-                        .unwrap_or(0);
-                    let thread_id = std::thread::current().id();
-                    let thread_hash = fxhash::hash64(&std::format!("{:?}", thread_id).as_bytes());
-                    let stack_addr = &nanos as *const _ as usize as u64;
-                    combined ^= nanos.wrapping_add(stack_addr).wrapping_mul(thread_hash);
-                }
-            }
+    /// Set probability of failure as the exact fraction `numerator / denominator`.
+    ///
+    /// The threshold is computed with integer math, so ratios like 1/3 that have no
+    /// exact float representation don't drift. Ratios above one are clamped to 100%.
+    ///
+    /// # Panics
+    /// Panics if `denominator` is zero.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(FailureConfig::new().with_ratio(1, 3).with_seed(42));
+    ///
+    /// let samples = 300_000;
+    /// let failures = (0..samples).filter(|_| should_simulate_failure(fp)).count();
+    /// let rate = failures as f64 / samples as f64;
+    /// assert!((rate - 1.0 / 3.0).abs() < 0.005, "observed rate {rate}");
+    /// ```
+    pub fn with_ratio(mut self, numerator: u64, denominator: u64) -> Self {
+        self.probability_range = None;
+        self.set_probability(ratio_threshold(numerator, denominator));
+        self
+    }
 
-            combined ^= combined >> 33;
-            combined = combined.wrapping_mul(0xff51afd7ed558ccd);
-            combined ^= combined >> 33;
-            combined = combined.wrapping_mul(0xc4ceb9fe1a85ec53);
-            combined ^= combined >> 33;
+    /// Get a handle for changing the probability of an installed config at runtime.
+    ///
+    /// The handle holds the raw threshold, where `u32::MAX` is 100%, and starts at the
+    /// probability set so far. Every check reads it, so a store through the handle
+    /// changes the live failure rate without reinstalling the config. Build thresholds
+    /// with `ratio_threshold()`.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::sync::atomic::Ordering;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let (config, handle) = FailureConfig::new().with_seed(3).with_probability_handle();
+    /// let _guard = with_thread_config(config);
+    /// assert!((0..1000).all(|_| !should_simulate_failure(fp)));
+    ///
+    /// handle.store(ratio_threshold(1, 2), Ordering::Relaxed);
+    /// let failures = (0..10_000).filter(|_| should_simulate_failure(fp)).count();
+    /// assert!((4_500..5_500).contains(&failures), "{failures} failures");
+    ///
+    /// handle.store(ratio_threshold(1, 1), Ordering::Relaxed);
+    /// assert!((0..1000).all(|_| should_simulate_failure(fp)));
+    /// ```
+    pub fn with_probability_handle(mut self) -> (Self, Arc<AtomicU32>) {
+        let handle = self
+            .probability_handle
+            .get_or_insert_with(|| Arc::new(AtomicU32::new(self.probability)))
+            .clone();
+        (self, handle)
+    }
 
-            let threshold = ((self.probability as u64) << 32) | self.probability as u64;
-            return combined < threshold;
+    fn set_probability(&mut self, threshold: u32) {
+        self.probability = threshold;
+        if let Some(handle) = &self.probability_handle {
+            handle.store(threshold, Ordering::Relaxed);
         }
+    }
 
-        false
+    /// Summarize the settings of this config in one line, for debugging.
+    ///
+    /// Closures can't be printed, so callbacks and predicates are only listed by name.
+    /// Use `{:?}` for every field, including counters.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let config = FailureConfig::new()
+    ///     .with_probability(0.25)
+    ///     .trigger_every(3)
+    ///     .with_seed(42)
+    ///     .enable_named("load")
+    ///     .enable_named("save")
+    ///     .on_failure(|_| {})
+    ///     .when(|| true);
+    ///
+    /// let description = config.describe();
+    /// assert!(description.contains("probability 25.0%"));
+    /// assert!(description.contains("trigger_every 3"));
+    /// assert!(description.contains("seed 42"));
+    /// assert!(description.contains("2 enabled points"));
+    /// assert!(description.contains("hooks [on_failure, when]"));
+    ///
+    /// let debug = format!("{config:?}");
+    /// assert!(debug.starts_with("FailureConfig {"));
+    /// assert!(debug.contains("seed: 42"));
+    /// ```
+    pub fn describe(&self) -> alloc::string::String {
+        use core::fmt::Write;
+
+        let mut out = alloc::format!("probability {:.1}%", self.probability_percent());
+        if let Some((lo, hi)) = self.probability_range {
+            let _ = write!(out, " (drawn from {:.1}%..={:.1}%)", lo * 100.0, hi * 100.0);
+        }
+        if self.trigger_every > 0 {
+            let scope = if self.trigger_every_global { "all points" } else { "per point" };
+            let _ = write!(out, ", trigger_every {} ({})", self.trigger_every, scope);
+            if self.trigger_offset > 0 {
+                let _ = write!(out, ", offset {}", self.trigger_offset);
+            }
+            if self.jitter > 0 {
+                let _ = write!(out, ", jitter ±{:.1}%", self.jitter as f64 / u32::MAX as f64 * 100.0);
+            }
+        }
+        if self.half_life > 0 {
+            let _ = write!(out, ", halving every {} checks", self.half_life);
+        }
+        for (file, threshold) in &self.file_probabilities {
+            let percent = *threshold as f64 / u32::MAX as f64 * 100.0;
+            let _ = write!(out, ", {:.1}% in files matching {:?}", percent, file);
+        }
+        for (id, threshold) in &self.point_probabilities {
+            let percent = *threshold as f64 / u32::MAX as f64 * 100.0;
+            let _ = write!(out, ", {:.1}% at point {:#010x}", percent, id.0);
+        }
+        if self.seed != 0 {
+            let _ = write!(out, ", seed {}", self.seed);
+        }
+        match self.enabled_points.len() {
+            0 => out.push_str(", all points enabled"),
+            1 => out.push_str(", 1 enabled point"),
+            n => {
+                let _ = write!(out, ", {} enabled points", n);
+            }
+        }
+        if self.max_failures > 0 {
+            let _ = write!(out, ", max_failures {}", self.max_failures);
+        }
+        if self.max_severity != Severity::Critical {
+            let _ = write!(out, ", max_severity {:?}", self.max_severity);
+        }
+        #[cfg(feature = "std")]
+        if self.mtbf_ns > 0 {
+            let _ = write!(out, ", mtbf {:?}", Duration::from_nanos(self.mtbf_ns));
+        }
+        #[cfg(feature = "std")]
+        if self.time_window.is_some() {
+            out.push_str(", time window");
+        }
+        if let Some(scenario) = &self.scenario {
+            let _ = write!(out, ", scenario of {} phases", scenario.phases.len());
+        }
+        let hooks = self.hook_names();
+        if !hooks.is_empty() {
+            let _ = write!(out, ", hooks [{}]", hooks.join(", "));
+        }
+        out
     }
-}
 
-impl Default for FailureConfig {
-    fn default() -> Self {
-        Self::new()
+    /// Get the probability as a percentage from 0.0 to 100.0.
+    fn probability_percent(&self) -> f64 {
+        self.current_probability() as f64 / u32::MAX as f64 * 100.0
     }
-}
 
-/// Set a custom global failure handler.
-///
-/// You should use `configure_failures()` instead unless you need complete control
-/// over failure behavior.
-pub fn set_global_handler<H: FailureHandler + 'static>(handler: H) {
-    let handler: Box<dyn FailureHandler> = Box::new(handler);
-    let ptr = Box::into_raw(handler);
+    /// Names of the callbacks and predicates that are set.
+    fn hook_names(&self) -> Vec<&'static str> {
+        [
+            ("on_check", self.on_check.is_some()),
+            ("on_failure", self.on_failure.is_some()),
+            ("on_nth_failure", !self.on_nth_failure.is_empty()),
+            ("on_failure_kind", self.on_failure_kind.is_some()),
+            ("on_failure_value", self.on_failure_value.is_some()),
+            ("failure_filter", self.failure_filter.is_some()),
+            ("when", self.predicate.is_some()),
+            ("when_point", self.point_predicate.is_some()),
+            ("counter_store", self.counter_store.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect()
+    }
 
-    let parts: [usize; 2] = unsafe { core::mem::transmute(ptr) };
+    /// Get the probability threshold, reading the live handle if there is one.
+    fn current_probability(&self) -> u32 {
+        match &self.probability_handle {
+            Some(handle) => handle.load(Ordering::Relaxed),
+            None => self.probability,
+        }
+    }
 
-    GLOBAL_HANDLER_DATA.store(parts[0], Ordering::SeqCst);
-    GLOBAL_HANDLER_VTABLE.store(parts[1], Ordering::SeqCst);
+    /// Set probability of failure in thousandths (0 to 1000).
+    ///
+    /// Computed without floating point, for `no_std` targets without an FPU and for
+    /// float-free config files. Values above 1000 are clamped to 100%.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// let per_mille = FailureConfig::new().with_probability_per_mille(250).snapshot();
+    /// let float = FailureConfig::new().with_probability(0.25).snapshot();
+    /// assert!(per_mille.probability.abs_diff(float.probability) <= 1);
+    /// ```
+    pub fn with_probability_per_mille(self, per_mille: u16) -> Self {
+        self.with_ratio(per_mille as u64, 1_000)
+    }
+
+    /// Set probability of failure in parts per million (0 to 1,000,000).
+    ///
+    /// Like `with_probability_per_mille()`, with finer resolution for rare failures.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// let ppm = FailureConfig::new().with_probability_ppm(1_500).snapshot();
+    /// let float = FailureConfig::new().with_probability(0.0015).snapshot();
+    /// assert!(ppm.probability.abs_diff(float.probability) <= 1);
+    /// ```
+    pub fn with_probability_ppm(self, ppm: u32) -> Self {
+        self.with_ratio(ppm as u64, 1_000_000)
+    }
+
+    /// Fail every nth call deterministically.
+    ///
+    /// Creates a predictable failure pattern for testing scenarios. Calls are counted
+    /// per failure point, so interleaved functions each fail on their own nth call.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let point = |id, function| FailurePoint::new(FailurePointId(id), function);
+    /// let (read, write) = (point(1, "read"), point(2, "write"));
+    ///
+    /// // Fail on calls 0, 3, 6, 9, ... of each point
+    /// let _guard = with_thread_config(FailureConfig::new().trigger_every(3));
+    ///
+    /// let calls: Vec<(bool, bool)> = (0..4)
+    ///     .map(|_| (should_simulate_failure(read), should_simulate_failure(write)))
+    ///     .collect();
+    /// assert_eq!(calls, [(true, true), (false, false), (false, false), (true, true)]);
+    /// ```
+    pub fn trigger_every(mut self, n: u64) -> Self {
+        self.trigger_every = n;
+        self.trigger_every_global = false;
+        self.trigger_offset = 0;
+        self
+    }
+
+    /// Fail every nth call, starting at call `offset` instead of the first call.
+    ///
+    /// Counts per failure point like `trigger_every()`, failing on calls `offset`,
+    /// `offset + n`, `offset + 2n`, ... so points with different offsets don't all
+    /// fail on the same call index. The offset is taken modulo `n`: an offset of
+    /// `n` or more fails on the calls of `offset % n`, starting before `offset`.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(FailureConfig::new().trigger_every_offset(5, 2));
+    ///
+    /// let failing: Vec<usize> = (0..13).filter(|_| should_simulate_failure(fp)).collect();
+    /// assert_eq!(failing, [2, 7, 12]);
+    /// ```
+    pub fn trigger_every_offset(mut self, n: u64, offset: u64) -> Self {
+        self.trigger_every = n;
+        self.trigger_every_global = false;
+        self.trigger_offset = offset;
+        self
+    }
+
+    /// Vary the `trigger_every` interval by up to `pct` of `n` either way.
+    ///
+    /// Each window of `n` calls still fails exactly once, so failures average one
+    /// in `n`, but the failing call moves within its window to a position drawn from
+    /// the seed, making the gaps between failures range over `n ± pct·n`. Runs with
+    /// the same seed jitter the same way. Applies to inline `trigger_every`
+    /// attributes too.
+    ///
+    /// # Panics
+    /// Panics if `pct` is not between 0.0 and 1.0.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new().trigger_every(10).with_jitter(0.4).with_seed(7),
+    /// );
+    ///
+    /// let failing: Vec<u64> = (0..1000).filter(|_| should_simulate_failure(fp)).collect();
+    /// let gaps: Vec<u64> = failing.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    ///
+    /// assert_eq!(failing.len(), 100);
+    /// let mean = gaps.iter().sum::<u64>() as f64 / gaps.len() as f64;
+    /// assert!((mean - 10.0).abs() < 0.1);
+    /// assert!(gaps.iter().all(|gap| (6..=14).contains(gap)));
+    /// assert!(gaps.iter().any(|&gap| gap != 10));
+    /// ```
+    pub fn with_jitter(mut self, pct: f64) -> Self {
+        assert!((0.0..=1.0).contains(&pct), "jitter must be between 0.0 and 1.0");
+        self.jitter = (pct * u32::MAX as f64) as u32;
+        self
+    }
+
+    /// Check whether call `count` is the one that fails in its `with_jitter()` window.
+    fn jittered_hit(&self, id: FailurePointId, count: u64, n: u64, offset: u64) -> bool {
+        let spread = (n as u128 * self.jitter as u128 / u32::MAX as u128 / 2) as u64;
+        let spread = spread.min((n - 1) / 2);
+        let shift = mix_hash(id.0, count / n, self.seed) % (2 * spread + 1);
+        count % n == (offset % n + shift) % n
+    }
+
+    /// Let this config's probability and trigger interval win over inline attributes.
+    ///
+    /// By default `#[fallible(probability = ..)]`, `ratio` and `trigger_every` replace the
+    /// config's values for their function. Precedence, highest first:
+    ///
+    /// | Source                                 | Applies to                       |
+    /// |----------------------------------------|----------------------------------|
+    /// | Config with `override_inline(true)`    | every point                      |
+    /// | Inline attributes                      | the annotated function           |
+    /// | Config probability and `trigger_every` | points without inline attributes |
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let never = InlinePolicy { probability: 0, trigger_every: 0, trigger_offset: 0, opt_in: false, args_hash: None };
+    ///
+    /// let guard = with_thread_config(FailureConfig::new().with_probability(1.0));
+    /// assert!(!should_simulate_failure_inline(fp, never));
+    /// drop(guard);
+    ///
+    /// let _guard = with_thread_config(FailureConfig::new().with_probability(1.0).override_inline(true));
+    /// assert!(should_simulate_failure_inline(fp, never));
+    /// ```
+    pub fn override_inline(mut self, enabled: bool) -> Self {
+        self.override_inline = enabled;
+        self
+    }
+
+    /// Fail every nth call across all failure points combined.
+    ///
+    /// Unlike `trigger_every()`, every check shares one counter, so interleaved
+    /// functions take turns advancing it.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let point = |id, function| FailurePoint::new(FailurePointId(id), function);
+    /// let (read, write) = (point(1, "read"), point(2, "write"));
+    ///
+    /// let _guard = with_thread_config(FailureConfig::new().trigger_every_global(2));
+    ///
+    /// let calls: Vec<(bool, bool)> = (0..2)
+    ///     .map(|_| (should_simulate_failure(read), should_simulate_failure(write)))
+    ///     .collect();
+    /// assert_eq!(calls, [(true, false), (true, false)]);
+    /// ```
+    pub fn trigger_every_global(mut self, n: u64) -> Self {
+        self.trigger_every = n;
+        self.trigger_every_global = true;
+        self.trigger_offset = 0;
+        self
+    }
+
+    /// Keep the per-point `trigger_every()` counters in `store` instead of in memory.
+    ///
+    /// Counts then outlive the config, so rebuilding it, or restarting the process
+    /// with a persistent store such as `FileCounterStore`, continues the same
+    /// failure pattern. Snapshots don't include counts kept in a store.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::collections::HashMap;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// #[derive(Clone, Default)]
+    /// struct MemoryStore(Arc<Mutex<HashMap<u32, u64>>>);
+    ///
+    /// impl CounterStore for MemoryStore {
+    ///     fn load(&self, id: FailurePointId) -> u64 {
+    ///         self.0.lock().unwrap().get(&id.0).copied().unwrap_or(0)
+    ///     }
+    ///
+    ///     fn save(&self, id: FailurePointId, count: u64) {
+    ///         self.0.lock().unwrap().insert(id.0, count);
+    ///     }
+    /// }
+    ///
+    /// let store = MemoryStore::default();
+    /// let config = || FailureConfig::new().trigger_every(3).with_counter_store(store.clone());
+    ///
+    /// let first: Vec<bool> = {
+    ///     let _guard = with_thread_config(config());
+    ///     (0..2).map(|_| should_simulate_failure(fp)).collect()
+    /// };
+    /// // A rebuilt config continues from the stored count
+    /// let second: Vec<bool> = {
+    ///     let _guard = with_thread_config(config());
+    ///     (0..2).map(|_| should_simulate_failure(fp)).collect()
+    /// };
+    /// assert_eq!([first, second].concat(), [true, false, false, true]);
+    /// assert_eq!(store.load(fp.id), 4);
+    /// ```
+    pub fn with_counter_store<S: CounterStore + 'static>(mut self, store: S) -> Self {
+        self.counter_store = Some(Box::new(store));
+        self
+    }
+
+    /// Set a seed for reproducible randomness.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// // Same seed always produces same failure pattern
+    /// let config = FailureConfig::new()
+    ///     .with_probability(0.3)
+    ///     .with_seed(12345);
+    /// ```
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.pick_probability();
+        self
+    }
+
+    /// Give every thread its own reproducible failure stream.
+    ///
+    /// With a shared seeded config, threads race on one counter, so no single thread's
+    /// sequence is reproducible. When enabled, each thread counts its own checks and
+    /// mixes its `thread_index()` into the seed, producing a distinct stream per thread
+    /// that is identical across runs. Has no effect without `with_seed()`.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::thread;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let config = || {
+    ///     FailureConfig::new()
+    ///         .with_probability(0.5)
+    ///         .with_seed(42)
+    ///         .with_per_thread_seed(true)
+    /// };
+    /// let run = |config| {
+    ///     let _guard = with_config(config);
+    ///     let handles: Vec<_> = (0..2)
+    ///         .map(|index| {
+    ///             thread::spawn(move || {
+    ///                 set_thread_index(index);
+    ///                 (0..64).map(|_| should_simulate_failure(fp)).collect::<Vec<_>>()
+    ///             })
+    ///         })
+    ///         .collect();
+    ///     handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+    /// };
+    ///
+    /// let first = run(config());
+    /// assert_eq!(run(config()), first);
+    /// assert_ne!(first[0], first[1]);
+    ///
+    /// // A restored snapshot produces the same per-thread streams
+    /// assert_eq!(run(FailureConfig::from_snapshot(&config().snapshot())), first);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_per_thread_seed(mut self, enabled: bool) -> Self {
+        self.per_thread_seed = enabled;
+        self
+    }
+
+    /// Set seed from `FALLIBLES_SEED` environment variable.
+    ///
+    /// If the environment variable is not set or invalid, uses default (0).
+    ///
+    /// # Example
+    /// ```bash
+    /// FALLIBLES_SEED=12345 cargo test
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_seed_from_env(mut self) -> Self {
+        if let Ok(seed_str) = std::env::var("FALLIBLES_SEED")
+            && let Ok(seed) = seed_str.parse::<u64>()
+        {
+            self.seed = seed;
+            self.pick_probability();
+        }
+        self
+    }
+
+    /// Seed from fresh entropy, picked once and kept for the config's lifetime.
+    ///
+    /// Runs still differ from each other like unseeded ones, but the chosen seed is
+    /// recorded in `describe()` and `snapshot()`, so a failing run can be printed and
+    /// replayed later with `with_seed()` or `FALLIBLES_SEED`. The seed is never 0.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let config = FailureConfig::new().with_probability(0.3).with_seed_from_entropy();
+    /// let seed = config.seed().unwrap();
+    /// assert_ne!(seed, 0);
+    /// assert_eq!(config.snapshot().seed, seed);
+    /// assert!(config.describe().contains(&format!("seed {seed}")));
+    ///
+    /// let first: Vec<bool> = {
+    ///     let _guard = with_thread_config(config);
+    ///     (0..50).map(|_| should_simulate_failure(fp)).collect()
+    /// };
+    /// // Replaying with the printed seed gives the same run
+    /// let _guard = with_thread_config(FailureConfig::new().with_probability(0.3).with_seed(seed));
+    /// assert_eq!((0..50).map(|_| should_simulate_failure(fp)).collect::<Vec<_>>(), first);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_seed_from_entropy(self) -> Self {
+        let seed = unseeded_entropy();
+        self.with_seed(if seed == 0 { 1 } else { seed })
+    }
+
+    /// Set a predicate that must return true for failures to occur.
+    ///
+    /// Allows control over when failures are enabled based on runtime conditions.
+    /// Checks the predicate rejects still count towards `total_checks`.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// // Only fail when chaos mode is enabled
+    /// let config = FailureConfig::new()
+    ///     .with_probability(0.5)
+    ///     .when(|| std::env::var("CHAOS_MODE").is_ok());
+    /// ```
+    ///
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(FailureConfig::enable_all().when(|| false));
+    /// for _ in 0..5 {
+    ///     assert!(!should_simulate_failure(fp));
+    /// }
+    ///
+    /// let stats = get_failure_stats().unwrap();
+    /// assert_eq!(stats.total_checks, 5);
+    /// assert_eq!(stats.total_failures, 0);
+    /// ```
+    pub fn when<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn() -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Some(Box::new(predicate));
+        self
+    }
+
+    /// Only fail while every one of `predicates` returns true.
+    ///
+    /// Predicates run in order and stop at the first that returns false. Replaces any
+    /// predicate set with `when()` or `when_any()`.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let chaos = Arc::new(AtomicBool::new(false));
+    /// let tenant_checks = Arc::new(AtomicUsize::new(0));
+    ///
+    /// let (flag, calls) = (chaos.clone(), tenant_checks.clone());
+    /// let _guard = with_thread_config(FailureConfig::enable_all().when_all(vec![
+    ///     Box::new(move || flag.load(Ordering::Relaxed)),
+    ///     Box::new(move || {
+    ///         calls.fetch_add(1, Ordering::Relaxed);
+    ///         true
+    ///     }),
+    /// ]));
+    ///
+    /// // The tenant check is skipped while chaos is off
+    /// assert!(!should_simulate_failure(fp));
+    /// assert_eq!(tenant_checks.load(Ordering::Relaxed), 0);
+    ///
+    /// chaos.store(true, Ordering::Relaxed);
+    /// assert!(should_simulate_failure(fp));
+    /// assert_eq!(tenant_checks.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn when_all(mut self, predicates: Vec<FailurePredicate>) -> Self {
+        self.predicate = Some(Box::new(move || predicates.iter().all(|predicate| predicate())));
+        self
+    }
+
+    /// Only fail while at least one of `predicates` returns true.
+    ///
+    /// Predicates run in order and stop at the first that returns true. An empty list
+    /// never fails. Replaces any predicate set with `when()` or `when_all()`.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let forced = Arc::new(AtomicBool::new(true));
+    /// let fallback_checks = Arc::new(AtomicUsize::new(0));
+    ///
+    /// let (flag, calls) = (forced.clone(), fallback_checks.clone());
+    /// let _guard = with_thread_config(FailureConfig::enable_all().when_any(vec![
+    ///     Box::new(move || flag.load(Ordering::Relaxed)),
+    ///     Box::new(move || {
+    ///         calls.fetch_add(1, Ordering::Relaxed);
+    ///         false
+    ///     }),
+    /// ]));
+    ///
+    /// // The fallback is skipped once the first predicate holds
+    /// assert!(should_simulate_failure(fp));
+    /// assert_eq!(fallback_checks.load(Ordering::Relaxed), 0);
+    ///
+    /// forced.store(false, Ordering::Relaxed);
+    /// assert!(!should_simulate_failure(fp));
+    /// assert_eq!(fallback_checks.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn when_any(mut self, predicates: Vec<FailurePredicate>) -> Self {
+        self.predicate = Some(Box::new(move || predicates.iter().any(|predicate| predicate())));
+        self
+    }
+
+    /// Set a predicate on the failure point that must return true for failures to occur.
+    ///
+    /// Like `when()`, but receives the point being checked, so chaos can be limited
+    /// to certain functions or files. Both predicates must pass when both are set.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let point = |function: &'static str| FailurePoint::named(function);
+    ///
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new()
+    ///         .with_probability(1.0)
+    ///         .when_point(|fp| fp.function.starts_with("db_")),
+    /// );
+    ///
+    /// assert!(should_simulate_failure(point("db_query")));
+    /// assert!(should_simulate_failure(point("db_write")));
+    /// assert!(!should_simulate_failure(point("render_page")));
+    /// ```
+    pub fn when_point<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(FailurePoint) -> bool + Send + Sync + 'static,
+    {
+        self.point_predicate = Some(Box::new(predicate));
+        self
+    }
+
+    /// Add artificial latency/delay to failure points.
+    ///
+    /// Adds a delay (in the specified range) every time a failure point is checked,
+    /// even if no failure is triggered. Useful for simulating slow networks or I/O.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// use std::time::Duration;
+    ///
+    /// // Add 10-50ms latency to all checks
+    /// let config = FailureConfig::new()
+    ///     .with_probability(0.2)
+    ///     .with_latency(Duration::from_millis(10), Duration::from_millis(50));
+    ///
+    /// // Fixed latency
+    /// let config = FailureConfig::new()
+    ///     .with_latency(Duration::from_millis(100), Duration::from_millis(100));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_latency(mut self, min: Duration, max: Duration) -> Self {
+        self.latency_min_ns = min.as_nanos() as u64;
+        self.latency_max_ns = max.as_nanos() as u64;
+        self
+    }
+
+    /// Add latency drawn from a tail-heavy profile to every check.
+    ///
+    /// Half the checks are delayed by less than `p50` and 1% by `p99`, the tail delay;
+    /// the rest are spread evenly between the two. Models slow outliers better than
+    /// `with_latency()`, which it replaces. Draws follow the seed when one is set.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::*;
+    /// use std::time::Duration;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let (p50, p99) = (Duration::from_millis(5), Duration::from_millis(500));
+    /// let config = FailureConfig::new().latency_profile(p50, p99).with_seed(7);
+    ///
+    /// let samples: Vec<Duration> = (0..100_000).map(|_| config.sample_latency(fp)).collect();
+    /// let share = |pred: &dyn Fn(&Duration) -> bool| {
+    ///     samples.iter().filter(|d| pred(d)).count() as f64 / samples.len() as f64
+    /// };
+    /// assert!((share(&|d| *d < p50) - 0.50).abs() < 0.01);
+    /// assert!((share(&|d| *d >= p99) - 0.01).abs() < 0.002);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn latency_profile(mut self, p50: Duration, p99: Duration) -> Self {
+        self.latency_p50_ns = p50.as_nanos() as u64;
+        self.latency_p99_ns = (p99.as_nanos() as u64).max(self.latency_p50_ns);
+        self
+    }
+
+    /// Draw one latency from the `latency_profile()`, without sleeping.
+    ///
+    /// Returns zero when no profile is set. Each draw advances the profile's stream,
+    /// just like a check does.
+    #[cfg(feature = "std")]
+    pub fn sample_latency(&self, fp: FailurePoint) -> Duration {
+        Duration::from_nanos(self.profile_latency_ns(fp))
+    }
+
+    #[cfg(feature = "std")]
+    fn profile_latency_ns(&self, fp: FailurePoint) -> u64 {
+        if self.latency_p99_ns == 0 {
+            return 0;
+        }
+
+        let sample = self.latency_samples.fetch_add(1, Ordering::Relaxed);
+        let mut bytes = [0u8; 20];
+        bytes[0..4].copy_from_slice(&fp.id.0.to_le_bytes());
+        bytes[4..12].copy_from_slice(&sample.to_le_bytes());
+        bytes[12..20].copy_from_slice(&self.seed.to_le_bytes());
+        let unit = (fxhash::hash64(&bytes) >> 11) as f64 / (1u64 << 53) as f64;
+
+        // Piecewise-linear quantile function through p50 and p99, flat above p99
+        let (p50, p99) = (self.latency_p50_ns as f64, self.latency_p99_ns as f64);
+        let latency = if unit < 0.5 {
+            p50 * unit / 0.5
+        } else if unit < 0.99 {
+            p50 + (p99 - p50) * (unit - 0.5) / 0.49
+        } else {
+            p99
+        };
+        latency as u64
+    }
+
+    /// Fail about once per `interval`, modeled as a mean time between failures.
+    ///
+    /// A check fails once `interval` has passed since the previous failure (or since
+    /// the config was created); every other check in between succeeds. Takes precedence
+    /// over `trigger_every()` and `with_probability()`.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::time::Duration;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let now_ms = Arc::new(AtomicU64::new(0));
+    /// let clock = now_ms.clone();
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new()
+    ///         .mtbf(Duration::from_secs(1))
+    ///         .with_clock(move || Duration::from_millis(clock.load(Ordering::Relaxed))),
+    /// );
+    ///
+    /// // One call every 10ms for 10 simulated seconds
+    /// let mut failures = 0;
+    /// for _ in 0..1000 {
+    ///     now_ms.fetch_add(10, Ordering::Relaxed);
+    ///     failures += should_simulate_failure(fp) as u32;
+    /// }
+    /// assert_eq!(failures, 10);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn mtbf(mut self, interval: Duration) -> Self {
+        self.mtbf_ns = interval.as_nanos() as u64;
+        self
+    }
+
+    /// Only inject failures between `start` and `end` in wall-clock time.
+    ///
+    /// Checks before `start` or from `end` on pass without counting toward intervals,
+    /// like checks blocked by a predicate. Useful for scheduled chaos experiments.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::time::{Duration, SystemTime};
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let hour = Duration::from_secs(3600);
+    /// let now = SystemTime::now();
+    ///
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new().with_probability(1.0).with_time_window(now - hour, now + hour),
+    /// );
+    /// assert!(should_simulate_failure(fp));
+    ///
+    /// // A window that already ended, and one that hasn't started
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new().with_probability(1.0).with_time_window(now - 2 * hour, now - hour),
+    /// );
+    /// assert!(!should_simulate_failure(fp));
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new().with_probability(1.0).with_time_window(now + hour, now + 2 * hour),
+    /// );
+    /// assert!(!should_simulate_failure(fp));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_time_window(
+        mut self,
+        start: std::time::SystemTime,
+        end: std::time::SystemTime,
+    ) -> Self {
+        self.time_window = Some((start, end));
+        self
+    }
+
+    /// Replace the clock used by `mtbf()`.
+    ///
+    /// The clock returns the time elapsed since a fixed start, which the config treats
+    /// as the time of the last failure. Useful for driving time-based failures from a
+    /// mock clock in tests.
+    #[cfg(feature = "std")]
+    pub fn with_clock<F>(mut self, clock: F) -> Self
+    where
+        F: Fn() -> Duration + Send + Sync + 'static,
+    {
+        self.clock = Some(Box::new(clock));
+        self
+    }
+
+    /// Only inject failures at points whose severity is at or below `level`.
+    ///
+    /// Points above the limit always succeed. By default every severity is eligible.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let point = |function: &'static str, severity| FailurePoint::named(function).with_severity(severity);
+    ///
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new()
+    ///         .with_probability(1.0)
+    ///         .max_severity(Severity::Medium),
+    /// );
+    ///
+    /// assert!(should_simulate_failure(point("cache_read", Severity::Low)));
+    /// assert!(should_simulate_failure(point("db_query", Severity::Medium)));
+    /// assert!(!should_simulate_failure(point("db_write", Severity::High)));
+    /// assert!(!should_simulate_failure(point("drop_table", Severity::Critical)));
+    /// ```
+    pub fn max_severity(mut self, level: Severity) -> Self {
+        self.max_severity = level;
+        self
+    }
+
+    /// Limit the total number of failures that can be triggered.
+    ///
+    /// Once this limit is reached, no more failures will occur even if
+    /// probability or other conditions would trigger them.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// // Allow at most 5 failures, then stop
+    /// let config = FailureConfig::new()
+    ///     .with_probability(0.5)
+    ///     .max_failures(5);
+    /// ```
+    pub fn max_failures(mut self, max: u64) -> Self {
+        self.max_failures = max;
+        self
+    }
+
+    /// Record the trigger decision of every check.
+    ///
+    /// The recorded sequence can be read back with `recorded_decisions()` and fed
+    /// into `replay()` to reproduce the exact same pass/fail pattern in a later run.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "flaky");
+    /// let guard = with_thread_config(FailureConfig::new().with_probability(0.5).record());
+    /// let first: Vec<bool> = (0..50).map(|_| should_simulate_failure(fp)).collect();
+    /// let decisions = get_recorded_decisions().unwrap();
+    /// drop(guard);
+    /// assert_eq!(decisions, first);
+    ///
+    /// let _guard = with_thread_config(FailureConfig::new().replay(decisions));
+    /// let second: Vec<bool> = (0..50).map(|_| should_simulate_failure(fp)).collect();
+    /// assert_eq!(first, second);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn record(mut self) -> Self {
+        self.recorded = Some(std::sync::Mutex::new(Vec::new()));
+        self
+    }
+
+    /// Get the decisions recorded so far, in check order.
+    ///
+    /// Returns an empty list unless `record()` was enabled.
+    #[cfg(feature = "std")]
+    pub fn recorded_decisions(&self) -> Vec<bool> {
+        match &self.recorded {
+            Some(recorded) => recorded.lock().map(|d| d.clone()).unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Count how many checks separate each failure from the one before it.
+    ///
+    /// Read the counts back with `gap_histogram()`, to see whether failures come in
+    /// clusters or spread out evenly.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "flaky");
+    /// let _guard = with_thread_config(FailureConfig::new().trigger_every(5).with_histogram());
+    /// for _ in 0..100 {
+    ///     should_simulate_failure(fp);
+    /// }
+    ///
+    /// // 20 failures, each 5 checks after the last
+    /// let histogram = get_gap_histogram().unwrap();
+    /// assert_eq!(histogram[5], 19);
+    /// assert_eq!(histogram.iter().sum::<u64>(), 19);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_histogram(mut self) -> Self {
+        self.gaps = Some(std::sync::Mutex::new(GapHistogram::default()));
+        self
+    }
+
+    /// Get the gaps between consecutive failures counted so far.
+    ///
+    /// Entry `n` counts failures that came `n` checks after the previous failure,
+    /// up to `GAP_HISTOGRAM_BUCKETS - 1`; the last entry also counts every longer
+    /// gap. The first failure has no gap. Empty unless `with_histogram()` was enabled.
+    #[cfg(feature = "std")]
+    pub fn gap_histogram(&self) -> Vec<u64> {
+        match &self.gaps {
+            Some(gaps) => gaps.lock().map(|gaps| gaps.buckets.clone()).unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Replay a previously recorded sequence of trigger decisions.
+    ///
+    /// Each check takes the next decision from the list instead of evaluating
+    /// probability, predicates or enabled points. Once the list is exhausted,
+    /// no more failures are triggered.
+    pub fn replay(mut self, decisions: Vec<bool>) -> Self {
+        self.replay = Some(decisions);
+        self
+    }
+
+    /// Allow this config to be installed in release builds.
+    ///
+    /// Builds without `debug_assertions` refuse to install failure configs so chaos
+    /// can't be enabled in production by accident. Set this to opt in deliberately.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # use fallibles::fallibles_core::__private::DEBUG_ASSERTIONS;
+    /// # let fp = FailurePoint::new(FailurePointId(1), "checkout");
+    /// // Refused in release builds
+    /// configure_thread_failures(FailureConfig::new().with_probability(1.0));
+    /// # assert_eq!(should_simulate_failure(fp), DEBUG_ASSERTIONS);
+    ///
+    /// // Installed in every build
+    /// let forced = FailureConfig::new().with_probability(1.0).force_in_release(true);
+    /// let snapshot = forced.snapshot();
+    /// configure_thread_failures(forced);
+    /// assert!(should_simulate_failure(fp));
+    ///
+    /// // Restoring a snapshot keeps the opt-in
+    /// configure_thread_failures(FailureConfig::from_snapshot(&snapshot));
+    /// assert!(should_simulate_failure(fp));
+    /// ```
+    pub fn force_in_release(mut self, force: bool) -> Self {
+        self.force_in_release = force;
+        self
+    }
+
+    /// Panic when a check that should always fail passes.
+    ///
+    /// A self-check for "must fail" tests: with the probability at 100% or
+    /// `trigger_every(1)`, and no predicate, scenario, replay, `mtbf()`, time window or gate that
+    /// could legitimately let a check through, a passing check means the injection
+    /// itself is broken. Strict mode turns that into a panic naming the point.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(FailureConfig::new().with_probability(1.0).strict(true));
+    /// assert!((0..10_000).all(|_| should_simulate_failure(fp)));
+    /// ```
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Register a callback that's called every time a failure point is checked.
+    /// The callback receives information about the failure point being checked.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// let config = FailureConfig::new()
+    ///     .with_probability(0.3)
+    ///     .on_check(|fp| println!("Checking: {}:{}", fp.file, fp.line));
+    /// ```
+    pub fn on_check<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(FailurePoint) + Send + Sync + 'static,
+    {
+        self.on_check = Some(Box::new(callback));
+        self
+    }
+
+    /// Register a callback that's called when a failure is actually triggered.
+    ///
+    /// Useful for logging, metrics, or coordinating failures across multiple points.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// let config = FailureConfig::new()
+    ///     .with_probability(0.3)
+    ///     .on_failure(|fp| eprintln!("FAILURE at {}:{}", fp.file, fp.line));
+    /// ```
+    pub fn on_failure<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(FailurePoint) + Send + Sync + 'static,
+    {
+        self.on_failure = Some(Box::new(callback));
+        self
+    }
+
+    /// Register a callback that's called once, when the `n`th failure is triggered.
+    ///
+    /// Failures are counted from 1 across all points, like `stats().total_failures`,
+    /// so the callback runs on exactly one failure even when several threads fail at
+    /// once. Useful for dumping state at a meaningful point without logging every
+    /// failure. Can be called several times for different counts.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let fired = Arc::new(Mutex::new(Vec::new()));
+    /// let log = fired.clone();
+    /// let config = FailureConfig::new().trigger_every(2).on_nth_failure(3, move |_| {
+    ///     log.lock().unwrap().push(get_failure_stats().unwrap().total_checks);
+    /// });
+    /// let _guard = with_thread_config(config);
+    ///
+    /// for _ in 0..20 {
+    ///     should_simulate_failure(fp);
+    /// }
+    /// // Failures land on checks 1, 3, 5, ...; the third on check 5
+    /// assert_eq!(*fired.lock().unwrap(), [5]);
+    /// ```
+    pub fn on_nth_failure<F>(mut self, n: u64, callback: F) -> Self
+    where
+        F: Fn(FailurePoint) + Send + Sync + 'static,
+    {
+        self.on_nth_failure.push((n, Box::new(callback)));
+        self
+    }
+
+    /// Register a callback that's called for every injected fault, with its kind.
+    ///
+    /// Where `on_failure()` only sees failed checks, this also sees injected latency,
+    /// so a single hook can tell a slowed call from a failed one. A check that is
+    /// delayed and then fails reports a `Delay` followed by an `Error`.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Duration;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let delay = Duration::from_millis(1);
+    /// let kinds = Arc::new(Mutex::new(Vec::new()));
+    /// let log = kinds.clone();
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::new()
+    ///         .trigger_every(2)
+    ///         .with_latency(delay, delay)
+    ///         .on_failure_kind(move |_, kind| log.lock().unwrap().push(kind)),
+    /// );
+    ///
+    /// assert!(should_simulate_failure(fp));
+    /// assert!(!should_simulate_failure(fp));
+    /// assert_eq!(
+    ///     *kinds.lock().unwrap(),
+    ///     [FailureKind::Delay(delay), FailureKind::Error, FailureKind::Delay(delay)]
+    /// );
+    /// ```
+    pub fn on_failure_kind<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(FailurePoint, FailureKind) + Send + Sync + 'static,
+    {
+        self.on_failure_kind = Some(Box::new(callback));
+        self
+    }
+
+    /// Register a callback that sees the error an injected failure returns.
+    ///
+    /// Called by `#[fallible]` functions and `fallible_step!` after the error is built
+    /// and before it's returned, with the error as `&dyn Debug`. Errors that don't
+    /// implement `Debug` and poisoned results are skipped.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// let config = FailureConfig::new()
+    ///     .with_probability(0.3)
+    ///     .on_failure_value(|fp, err| eprintln!("{} returned {:?}", fp.function, err));
+    /// ```
+    pub fn on_failure_value<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(FailurePoint, &dyn core::fmt::Debug) + Send + Sync + 'static,
+    {
+        self.on_failure_value = Some(Box::new(callback));
+        self
+    }
+
+    /// Write every injected failure to `writer` as a line of JSON.
+    ///
+    /// Each line is a serialized `FailureEvent`. Runs alongside `on_failure()`; write
+    /// errors are ignored so logging can't change the outcome of a check.
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "json")]
+    /// # {
+    /// use fallibles::fallibles_core::*;
+    /// use std::io::Write;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// #[derive(Clone, Default)]
+    /// struct Buffer(Arc<Mutex<Vec<u8>>>);
+    ///
+    /// impl Write for Buffer {
+    ///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    ///         self.0.lock().unwrap().write(buf)
+    ///     }
+    ///     fn flush(&mut self) -> std::io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let buffer = Buffer::default();
+    /// let _guard = with_thread_config(FailureConfig::new().trigger_every(2).log_json(buffer.clone()));
+    /// for _ in 0..4 {
+    ///     should_simulate_failure(fp);
+    /// }
+    ///
+    /// let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    /// let events: Vec<serde_json::Value> = output
+    ///     .lines()
+    ///     .map(|line| serde_json::from_str(line).unwrap())
+    ///     .collect();
+    /// assert_eq!(events.len(), 2);
+    /// assert_eq!(events[0]["id"], 1);
+    /// assert_eq!(events[0]["function"], "db_query");
+    /// assert_eq!(events[0]["line"], fp.line);
+    /// assert!(events[0]["timestamp_ns"].as_u64().unwrap() > 0);
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn log_json<W>(mut self, writer: W) -> Self
+    where
+        W: std::io::Write + Send + 'static,
+    {
+        self.json_log = Some(std::sync::Mutex::new(Box::new(writer)));
+        self
+    }
+
+    /// Register a filter that can veto a failure after it was triggered.
+    ///
+    /// Runs once the config has decided to fail; returning `false` suppresses the failure
+    /// and counts it in `suppressed_failures`. Unlike `when()`, which gates every check,
+    /// the filter sees the failure point and only runs for would-be failures.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let point = |line| FailurePoint { line, column: 1, ..FailurePoint::new(FailurePointId(1), "commit") };
+    /// let _guard = with_thread_config(
+    ///     FailureConfig::enable_all().failure_filter(|fp| fp.line != 42),
+    /// );
+    ///
+    /// assert!(should_simulate_failure(point(10)));
+    /// assert!(!should_simulate_failure(point(42)));
+    ///
+    /// let stats = get_failure_stats().unwrap();
+    /// assert_eq!(stats.total_failures, 1);
+    /// assert_eq!(stats.suppressed_failures, 1);
+    /// ```
+    pub fn failure_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(FailurePoint) -> bool + Send + Sync + 'static,
+    {
+        self.failure_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// The seed set by `with_seed()`, `with_seed_from_env()` or `with_seed_from_entropy()`.
+    ///
+    /// Returns `None` for unseeded configs, whose checks draw fresh entropy each run.
+    /// `deterministic()` configs are reproducible with any seed, so they return it
+    /// even when it's 0.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// assert_eq!(FailureConfig::new().seed(), None);
+    /// assert_eq!(FailureConfig::new().with_seed(42).seed(), Some(42));
+    /// assert_eq!(FailureConfig::new().with_seed(0).seed(), None);
+    /// assert_eq!(FailureConfig::deterministic(0, 0.5).seed(), Some(0));
+    /// ```
+    pub fn seed(&self) -> Option<u64> {
+        (self.seed != 0 || self.deterministic).then_some(self.seed)
+    }
+
+    /// Get statistics about failure injection behavior.
+    ///
+    /// Returns total checks and total failures triggered.
+    ///
+    /// # Example
+    /// ```
+    /// # use fallibles::fallibles_core::FailureConfig;
+    /// # let config = FailureConfig::new();
+    /// let stats = config.stats();
+    /// println!("Failure rate: {}/{}", stats.total_failures, stats.total_checks);
+    /// ```
+    pub fn stats(&self) -> FailureStats {
+        FailureStats {
+            total_checks: self.checks_seen.load(Ordering::Relaxed),
+            total_failures: self.failures_triggered.load(Ordering::Relaxed),
+            limited_failures: self.limited_failures.load(Ordering::Relaxed),
+            suppressed_failures: self.suppressed_failures.load(Ordering::Relaxed),
+            #[cfg(feature = "std")]
+            total_latency_ns: self.total_latency_ns.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Capture the plain settings and counters of this config.
+    ///
+    /// Callbacks, the `when()` and `when_point()` predicates, scenarios and
+    /// `arm_point_after()` gates are not part of the snapshot.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// let config = FailureConfig::new()
+    ///     .with_probability(0.3)
+    ///     .with_seed(12345)
+    ///     .max_failures(10);
+    ///
+    /// let snapshot = config.snapshot();
+    /// let restored = FailureConfig::from_snapshot(&snapshot);
+    /// assert_eq!(restored.snapshot(), snapshot);
+    ///
+    /// // Seed 0 of a deterministic config survives the round trip
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let run = |config| {
+    ///     let _guard = with_thread_config(config);
+    ///     (0..32).map(|_| should_simulate_failure(fp)).collect::<Vec<_>>()
+    /// };
+    /// let original = FailureConfig::deterministic(0, 0.3);
+    /// let restored = FailureConfig::from_snapshot(&original.snapshot());
+    /// assert_eq!(run(restored), run(original));
+    ///
+    /// #[cfg(feature = "serde")]
+    /// {
+    ///     let json = serde_json::to_string(&snapshot).unwrap();
+    ///     let parsed = serde_json::from_str(&json).unwrap();
+    ///     assert_eq!(snapshot, parsed);
+    /// }
+    /// ```
+    pub fn snapshot(&self) -> FailureSnapshot {
+        FailureSnapshot {
+            enabled_points: self.enabled_points.clone(),
+            probability: self.current_probability(),
+            file_probabilities: self.file_probabilities.clone(),
+            point_probabilities: self.point_probabilities.clone(),
+            half_life: self.half_life,
+            trigger_every: self.trigger_every,
+            trigger_every_global: self.trigger_every_global,
+            trigger_offset: self.trigger_offset,
+            jitter: self.jitter,
+            override_inline: self.override_inline,
+            seed: self.seed,
+            deterministic: self.deterministic,
+            #[cfg(feature = "std")]
+            per_thread_seed: self.per_thread_seed,
+            force_in_release: self.force_in_release,
+            max_failures: self.max_failures,
+            max_severity: self.max_severity,
+            #[cfg(feature = "std")]
+            latency_min_ns: self.latency_min_ns,
+            #[cfg(feature = "std")]
+            latency_max_ns: self.latency_max_ns,
+            #[cfg(feature = "std")]
+            latency_p50_ns: self.latency_p50_ns,
+            #[cfg(feature = "std")]
+            latency_p99_ns: self.latency_p99_ns,
+            #[cfg(feature = "std")]
+            mtbf_ns: self.mtbf_ns,
+            #[cfg(feature = "std")]
+            time_window_ns: self.time_window.map(|(start, end)| {
+                let ns = |at: std::time::SystemTime| {
+                    at.duration_since(std::time::UNIX_EPOCH)
+                        .map(|d| d.as_nanos() as u64)
+                        .unwrap_or(0)
+                };
+                (ns(start), ns(end))
+            }),
+            counter: self.counter.load(Ordering::Relaxed),
+            checks_seen: self.checks_seen.load(Ordering::Relaxed),
+            failures_triggered: self.failures_triggered.load(Ordering::Relaxed),
+            limited_failures: self.limited_failures.load(Ordering::Relaxed),
+            point_counts: {
+                let mut counts = self.point_counts.entries();
+                counts.sort_unstable_by_key(|(id, _)| id.0);
+                counts
+            },
+        }
+    }
+
+    /// Rebuild a config from a snapshot, including its counters.
+    ///
+    /// Callbacks and predicates are not restored; add them again with the
+    /// usual builder methods if needed.
+    pub fn from_snapshot(snapshot: &FailureSnapshot) -> Self {
+        let mut enabled_points = snapshot.enabled_points.clone();
+        enabled_points.sort_unstable_by_key(|point| point.0);
+        enabled_points.dedup();
+
+        let config = Self {
+            enabled_points,
+            probability: snapshot.probability,
+            file_probabilities: snapshot.file_probabilities.clone(),
+            point_probabilities: snapshot.point_probabilities.clone(),
+            half_life: snapshot.half_life,
+            counter: AtomicU64::new(snapshot.counter),
+            checks_seen: AtomicU64::new(snapshot.checks_seen),
+            trigger_every: snapshot.trigger_every,
+            trigger_every_global: snapshot.trigger_every_global,
+            trigger_offset: snapshot.trigger_offset,
+            jitter: snapshot.jitter,
+            override_inline: snapshot.override_inline,
+            failures_triggered: AtomicU64::new(snapshot.failures_triggered),
+            seed: snapshot.seed,
+            deterministic: snapshot.deterministic,
+            #[cfg(feature = "std")]
+            per_thread_seed: snapshot.per_thread_seed,
+            force_in_release: snapshot.force_in_release,
+            #[cfg(feature = "std")]
+            latency_min_ns: snapshot.latency_min_ns,
+            #[cfg(feature = "std")]
+            latency_max_ns: snapshot.latency_max_ns,
+            #[cfg(feature = "std")]
+            latency_p50_ns: snapshot.latency_p50_ns,
+            #[cfg(feature = "std")]
+            latency_p99_ns: snapshot.latency_p99_ns,
+            #[cfg(feature = "std")]
+            mtbf_ns: snapshot.mtbf_ns,
+            #[cfg(feature = "std")]
+            time_window: snapshot.time_window_ns.map(|(start, end)| {
+                let at = |ns| std::time::UNIX_EPOCH + Duration::from_nanos(ns);
+                (at(start), at(end))
+            }),
+            max_failures: snapshot.max_failures,
+            limited_failures: AtomicU64::new(snapshot.limited_failures),
+            max_severity: snapshot.max_severity,
+            ..Self::new()
+        };
+        for &(id, count) in &snapshot.point_counts {
+            config.point_counts.set(id, count);
+        }
+        config
+    }
+
+    /// Copy the plain settings of this config into a fresh one.
+    ///
+    /// Probability, trigger interval, seed, enabled points, limits and latency are kept,
+    /// while counters start again from zero. Callbacks and predicates can't
+    /// be cloned and are dropped. Useful for deriving per-test variants of a base config.
+    ///
+    /// # Example
+    /// ```
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let base = FailureConfig::new().with_probability(0.5).with_seed(7);
+    /// let variant = base.clone_settings();
+    ///
+    /// let run = |config| {
+    ///     let _guard = with_thread_config(config);
+    ///     (0..32).map(|_| should_simulate_failure(fp)).collect::<Vec<_>>()
+    /// };
+    ///
+    /// let first = run(base);
+    /// assert_eq!(variant.stats().total_checks, 0);
+    /// assert_eq!(run(variant), first);
+    /// ```
+    pub fn clone_settings(&self) -> Self {
+        Self {
+            enabled_points: self.enabled_points.clone(),
+            probability: self.current_probability(),
+            probability_range: self.probability_range,
+            file_probabilities: self.file_probabilities.clone(),
+            point_probabilities: self.point_probabilities.clone(),
+            half_life: self.half_life,
+            trigger_every: self.trigger_every,
+            trigger_every_global: self.trigger_every_global,
+            trigger_offset: self.trigger_offset,
+            jitter: self.jitter,
+            override_inline: self.override_inline,
+            seed: self.seed,
+            #[cfg(feature = "std")]
+            latency_min_ns: self.latency_min_ns,
+            #[cfg(feature = "std")]
+            latency_max_ns: self.latency_max_ns,
+            #[cfg(feature = "std")]
+            latency_p50_ns: self.latency_p50_ns,
+            #[cfg(feature = "std")]
+            latency_p99_ns: self.latency_p99_ns,
+            #[cfg(feature = "std")]
+            mtbf_ns: self.mtbf_ns,
+            #[cfg(feature = "std")]
+            time_window: self.time_window,
+            max_failures: self.max_failures,
+            max_severity: self.max_severity,
+            #[cfg(feature = "std")]
+            recorded: self.recorded.as_ref().map(|_| std::sync::Mutex::new(Vec::new())),
+            #[cfg(feature = "std")]
+            gaps: self.gaps.as_ref().map(|_| std::sync::Mutex::new(GapHistogram::default())),
+            replay: self.replay.clone(),
+            force_in_release: self.force_in_release,
+            strict: self.strict,
+            deterministic: self.deterministic,
+            scenario: self.scenario.as_ref().map(FailureScenario::clone_settings),
+            arm_after: self.arm_after.clone(),
+            passed_points: self.passed_points.as_ref().map(|_| Box::new(PointTable::new())),
+            sequences: self.sequences.clone(),
+            sequence_counts: self.sequence_counts.as_ref().map(|_| Box::new(PointTable::new())),
+            #[cfg(feature = "std")]
+            per_thread_seed: self.per_thread_seed,
+            ..Self::new()
+        }
+    }
+
+    /// Get the counter and seed to hash for this check.
+    ///
+    /// With `with_per_thread_seed`, the shared counter is replaced by the current
+    /// thread's own count and the seed is mixed with its index.
+    #[cfg(feature = "std")]
+    fn thread_stream(&self, counter: u64) -> (u64, u64) {
+        if !self.per_thread_seed || self.seed == 0 {
+            return (counter, self.seed);
+        }
+
+        let count = THREAD_STREAM.with(|cell| {
+            let (config_id, count) = cell.get();
+            let count = if config_id == self.id { count } else { 0 };
+            cell.set((self.id, count + 1));
+            count
+        });
+        let seed = self.seed ^ thread_index().wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15);
+        (count, seed)
+    }
+
+    /// Read the `mtbf()` clock in nanoseconds.
+    #[cfg(feature = "std")]
+    fn now_ns(&self) -> u64 {
+        let elapsed = match &self.clock {
+            Some(clock) => clock(),
+            None => self.started.elapsed(),
+        };
+        elapsed.as_nanos() as u64
+    }
+
+    /// Get the trigger mode that applies to a check of `fp`.
+    fn mode(&self, fp: FailurePoint, inline: Option<InlinePolicy>) -> TriggerMode {
+        match inline {
+            Some(policy) if !self.override_inline && !policy.defers_rate() => TriggerMode::new(
+                policy.trigger_every,
+                false,
+                policy.trigger_offset,
+                policy.probability,
+            ),
+            _ => TriggerMode::new(
+                self.trigger_every,
+                self.trigger_every_global,
+                self.trigger_offset,
+                self.decayed(self.probability_at(fp)),
+            ),
+        }
+    }
+
+    /// The probability threshold for `fp`: its own from `with_point_probability()`,
+    /// then its file's from `with_probability_for_file()`, then the config's.
+    fn probability_at(&self, fp: FailurePoint) -> u32 {
+        if let Ok(index) = self.point_probabilities.binary_search_by_key(&fp.id.0, |(id, _)| id.0) {
+            return self.point_probabilities[index].1;
+        }
+        self.file_probabilities
+            .iter()
+            .find(|(file, _)| fp.file.contains(file.as_str()))
+            .map_or_else(|| self.current_probability(), |(_, threshold)| *threshold)
+    }
+
+    /// Halve `threshold` once for every `half_life` checks seen so far.
+    fn decayed(&self, threshold: u32) -> u32 {
+        if self.half_life == 0 {
+            return threshold;
+        }
+        let halvings = self.counter.load(Ordering::Relaxed) / self.half_life;
+        threshold.checked_shr(halvings.min(u32::BITS as u64) as u32).unwrap_or(0)
+    }
+
+    /// Check whether nothing in this config can keep a check of `fp` from failing.
+    fn must_fail(&self, fp: FailurePoint, inline: Option<InlinePolicy>) -> bool {
+        let maxed = self.mode(fp, inline).always_fails();
+        #[cfg(feature = "std")]
+        let timed = self.mtbf_ns > 0 || self.time_window.is_some();
+        #[cfg(not(feature = "std"))]
+        let timed = false;
+
+        maxed
+            && !timed
+            && self.replay.is_none()
+            && self.scenario.is_none()
+            && self.predicate.is_none()
+            && self.point_predicate.is_none()
+            && self.arm_after.is_empty()
+            && self.sequences.is_empty()
+            && fp.severity <= self.max_severity
+            && self.point_enabled(fp.id)
+            && !(inline.is_some_and(|policy| policy.opt_in) && self.enabled_points.is_empty())
+    }
+
+    fn should_trigger(&self, fp: FailurePoint, inline: Option<InlinePolicy>) -> bool {
+        let fp_id = fp.id;
+
+        if let Some(decisions) = &self.replay {
+            let index = self.counter.fetch_add(1, Ordering::Relaxed);
+            return decisions.get(index as usize).copied().unwrap_or(false);
+        }
+
+        if let Some(predicate) = &self.predicate
+            && !predicate() {
+                return false;
+            }
+
+        if let Some(predicate) = &self.point_predicate
+            && !predicate(fp)
+        {
+            return false;
+        }
+
+        if fp.severity > self.max_severity {
+            return false;
+        }
+
+        #[cfg(feature = "std")]
+        if let Some((start, end)) = self.time_window {
+            let now = std::time::SystemTime::now();
+            if now < start || now >= end {
+                return false;
+            }
+        }
+
+        if !self.point_enabled(fp_id) {
+            return false;
+        }
+
+        if inline.is_some_and(|policy| policy.opt_in) && self.enabled_points.is_empty() {
+            return false;
+        }
+
+        if !self.point_armed(fp_id) {
+            return false;
+        }
+
+        if let Some(outcome) = self.next_outcome(fp_id) {
+            self.counter.fetch_add(1, Ordering::Relaxed);
+            #[cfg(feature = "std")]
+            if let Outcome::ErrVariant(name) = outcome {
+                REQUESTED_VARIANT.with(|cell| cell.set(Some(name)));
+            }
+            return outcome != Outcome::Ok;
+        }
+
+        if let Some(scenario) = &self.scenario {
+            let index = self.counter.fetch_add(1, Ordering::Relaxed);
+            let inline = if self.override_inline { None } else { inline };
+            return scenario
+                .phase_at(index)
+                .is_some_and(|phase| phase.should_trigger(fp, inline));
+        }
+
+        #[cfg(feature = "std")]
+        if self.mtbf_ns > 0 {
+            self.counter.fetch_add(1, Ordering::Relaxed);
+            let now = self.now_ns();
+            let last = self.last_failure_ns.load(Ordering::Relaxed);
+            return now.saturating_sub(last) >= self.mtbf_ns
+                && self
+                    .last_failure_ns
+                    .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok();
+        }
+
+        match self.mode(fp, inline) {
+            TriggerMode::Never => false,
+            TriggerMode::Always => {
+                self.counter.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            TriggerMode::Every { n, global, offset } => {
+                let mut count = self.counter.fetch_add(1, Ordering::Relaxed);
+                if !global {
+                    if let Some(store) = &self.counter_store {
+                        count = store.load(fp_id);
+                        store.save(fp_id, count + 1);
+                    } else if let Some(point_count) = self.point_counts.increment(fp_id) {
+                        count = point_count;
+                    }
+                }
+                if self.jitter > 0 {
+                    let id = if global { FailurePointId(0) } else { fp_id };
+                    return self.jittered_hit(id, count, n, offset);
+                }
+                count % n == offset % n
+            }
+            TriggerMode::Probability(probability) => {
+                let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+                let threshold = ((probability as u64) << 32) | probability as u64;
+                if let Some(args_hash) = inline.and_then(|policy| policy.args_hash) {
+                    return mix_hash(fp_id.0, args_hash, self.seed) < threshold;
+                }
+
+                #[cfg(feature = "std")]
+                let (counter, seed) = self.thread_stream(counter);
+                #[cfg(not(feature = "std"))]
+                let seed = self.seed;
+
+                #[cfg(feature = "std")]
+                let seed = if seed != 0 || self.deterministic { seed } else { unseeded_entropy() };
+
+                let combined = mix_hash(fp_id.0, counter, seed);
+                combined < threshold
+            }
+        }
+    }
+}
+
+/// Get the failure point being checked or failed on the current thread.
+///
+/// Set while a check runs, so `on_check()`, `on_failure()` and other hooks can read
+/// it, and while `#[fallible]` builds the injected error, so plain
+/// `FallibleError::simulated_failure()` impls can describe the point without being
+/// passed it. `None` anywhere else.
+///
+/// # Example
+/// ```
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// let _guard = with_thread_config(
+///     FailureConfig::enable_all()
+///         .on_failure(|_| assert_eq!(current_point().unwrap().function, "db_query")),
+/// );
+///
+/// assert!(should_simulate_failure(fp));
+/// assert!(current_point().is_none());
+/// ```
+#[cfg(feature = "std")]
+pub fn current_point() -> Option<FailurePoint> {
+    CURRENT_POINT.with(Cell::get)
+}
+
+/// Get the enum variant an `Outcome::ErrVariant` asked for, if any.
+///
+/// Set from a failing `FailureConfig::sequence()` check until the injected error
+/// has been built. Derived `FallibleError` enums return the variant with this name
+/// when they have one; hand-written impls can match on it the same way. Always
+/// `None` without the `std` feature.
+///
+/// # Example
+/// ```
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// let _guard = with_thread_config(
+///     FailureConfig::new().sequence(fp.id, vec![Outcome::ErrVariant("Timeout")]),
+/// );
+///
+/// assert!(should_simulate_failure(fp));
+/// assert_eq!(requested_variant(), Some("Timeout"));
+/// ```
+pub fn requested_variant() -> Option<&'static str> {
+    #[cfg(feature = "std")]
+    return REQUESTED_VARIANT.with(Cell::get);
+    #[cfg(not(feature = "std"))]
+    None
+}
+
+/// Makes a point the `current_point()` until dropped, then restores the previous one.
+#[doc(hidden)]
+pub struct PointScope {
+    #[cfg(feature = "std")]
+    previous: Option<FailurePoint>,
+    #[cfg(feature = "std")]
+    building: bool,
+}
+
+impl PointScope {
+    pub fn enter(fp: FailurePoint) -> Self {
+        Self::new(fp, false)
+    }
+
+    /// Like `enter()`, for building the injected error; also clears
+    /// `requested_variant()` when dropped.
+    pub fn build(fp: FailurePoint) -> Self {
+        Self::new(fp, true)
+    }
+
+    fn new(fp: FailurePoint, building: bool) -> Self {
+        #[cfg(feature = "std")]
+        return Self {
+            previous: CURRENT_POINT.with(|cell| cell.replace(Some(fp))),
+            building,
+        };
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = (fp, building);
+            Self {}
+        }
+    }
+}
+
+impl Drop for PointScope {
+    fn drop(&mut self) {
+        #[cfg(feature = "std")]
+        {
+            CURRENT_POINT.with(|cell| cell.set(self.previous));
+            if self.building {
+                REQUESTED_VARIANT.with(|cell| cell.set(None));
+            }
+        }
+    }
+}
+
+/// Pass an injected error to the active config's `on_failure_value()` hook.
+fn report_failure_value(fp: FailurePoint, value: &dyn core::fmt::Debug) {
+    with_active_config(|config| {
+        if let Some(callback) = &config.on_failure_value {
+            callback(fp, value);
+        }
+    });
+}
+
+/// Hash a check into the 64-bit value compared against the probability threshold.
+///
+/// The point id (little-endian `u32`) and counter (little-endian `u64`, or the
+/// argument hash for `#[fallible(by_args)]` points) are hashed
+/// with both `fxhash::hash32` and `fxhash::hash64`, the two results are XORed, the
+/// seed multiplied by `0x517cc1b727220a95` is XORed in, and the result goes through
+/// the MurmurHash3 `fmix64` finalizer. A check fails when the result is below
+/// `(threshold << 32) | threshold`. A threshold of `u32::MAX` never reaches the hash:
+/// configs treat it as always failing.
+///
+/// The output is part of what makes seeded runs reproducible and won't change
+/// between patch releases.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::mix_hash;
+///
+/// assert_eq!(mix_hash(0, 0, 0), 0x2544b4e87bbb8afe);
+/// assert_eq!(mix_hash(1, 0, 42), 0x9a99baf86e0fe138);
+/// assert_eq!(mix_hash(1, 1, 42), 0x1e1be542c2fe65fb);
+/// assert_eq!(mix_hash(0xdead_beef, 12345, 12345), 0xea2d3a13e628035f);
+/// ```
+pub fn mix_hash(id: u32, counter: u64, seed: u64) -> u64 {
+    let mut bytes = [0u8; 12];
+    bytes[0..4].copy_from_slice(&id.to_le_bytes());
+    bytes[4..12].copy_from_slice(&counter.to_le_bytes());
+
+    let mut combined = (fxhash::hash32(&bytes) as u64) ^ fxhash::hash64(&bytes);
+    combined ^= seed.wrapping_mul(0x517cc1b727220a95);
+
+    combined ^= combined >> 33;
+    combined = combined.wrapping_mul(0xff51afd7ed558ccd);
+    combined ^= combined >> 33;
+    combined = combined.wrapping_mul(0xc4ceb9fe1a85ec53);
+    combined ^= combined >> 33;
+    combined
+}
+
+/// Number of entries in `FailureConfig::gap_histogram()`.
+#[cfg(feature = "std")]
+pub const GAP_HISTOGRAM_BUCKETS: usize = 64;
+
+/// Checks between consecutive failures, for `FailureConfig::with_histogram()`.
+#[cfg(feature = "std")]
+struct GapHistogram {
+    /// Checks seen when the last failure triggered, if any has
+    last_failure: Option<u64>,
+    buckets: Vec<u64>,
+}
+
+#[cfg(feature = "std")]
+impl Default for GapHistogram {
+    fn default() -> Self {
+        Self { last_failure: None, buckets: alloc::vec![0; GAP_HISTOGRAM_BUCKETS] }
+    }
+}
+
+#[cfg(feature = "std")]
+impl GapHistogram {
+    /// Count a failure on the check that brought the total to `checks`.
+    fn record(&mut self, checks: u64) {
+        if let Some(last) = self.last_failure {
+            let gap = checks.saturating_sub(last).min(GAP_HISTOGRAM_BUCKETS as u64 - 1);
+            self.buckets[gap as usize] += 1;
+        }
+        self.last_failure = Some(checks);
+    }
+}
+
+/// Stand-in seed for unseeded configs, varying by time, thread and stack address.
+#[cfg(feature = "std")]
+fn unseeded_entropy() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let thread_id = std::thread::current().id();
+    let thread_hash = fxhash::hash64(&std::format!("{:?}", thread_id).as_bytes());
+    let stack_addr = &nanos as *const _ as usize as u64;
+    nanos.wrapping_add(stack_addr).wrapping_mul(thread_hash)
+}
+
+/// Convert a probability from 0.0 to 1.0 into a threshold where `u32::MAX` is 100%.
+///
+/// Used by `FailureConfig::with_probability` and the `#[fallible(probability = ..)]`
+/// attribute, so an inline probability fails exactly like the same config probability.
+/// Values outside `0.0..=1.0` saturate.
+///
+/// # Example
+/// ```
+/// # use fallibles::fallibles_core::probability_threshold;
+/// assert_eq!(probability_threshold(1.0), u32::MAX);
+/// assert_eq!(probability_threshold(0.0), 0);
+/// assert_eq!(probability_threshold(0.5), u32::MAX / 2);
+/// assert_eq!(probability_threshold(2.0), u32::MAX);
+/// ```
+pub fn probability_threshold(prob: f64) -> u32 {
+    (prob * u32::MAX as f64) as u32
+}
+
+/// Convert `numerator / denominator` into a probability threshold where `u32::MAX` is 100%.
+///
+/// Used by `FailureConfig::with_ratio` and the `#[fallible(ratio = "...")]` attribute.
+///
+/// # Panics
+/// Panics if `denominator` is zero.
+///
+/// # Example
+/// ```
+/// # use fallibles::fallibles_core::ratio_threshold;
+/// assert_eq!(ratio_threshold(1, 1), u32::MAX);
+/// assert_eq!(ratio_threshold(1, 3), u32::MAX / 3);
+/// assert_eq!(ratio_threshold(0, 5), 0);
+/// ```
+pub const fn ratio_threshold(numerator: u64, denominator: u64) -> u32 {
+    assert!(denominator != 0, "ratio denominator must not be zero");
+    if numerator >= denominator {
+        return u32::MAX;
+    }
+    (numerator as u128 * u32::MAX as u128 / denominator as u128) as u32
+}
+
+impl Default for FailureConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for FailureConfig {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut debug = f.debug_struct("FailureConfig");
+        debug
+            .field("probability", &format_args!("{:.1}%", self.probability_percent()))
+            .field("probability_range", &self.probability_range)
+            .field("file_probabilities", &self.file_probabilities.len())
+            .field("point_probabilities", &self.point_probabilities.len())
+            .field("half_life", &self.half_life)
+            .field("trigger_every", &self.trigger_every)
+            .field("trigger_every_global", &self.trigger_every_global)
+            .field("trigger_offset", &self.trigger_offset)
+            .field("jitter", &self.jitter)
+            .field("override_inline", &self.override_inline)
+            .field("strict", &self.strict)
+            .field("deterministic", &self.deterministic)
+            .field("seed", &self.seed)
+            .field("enabled_points", &self.enabled_points.len())
+            .field("max_failures", &self.max_failures)
+            .field("max_severity", &self.max_severity);
+        #[cfg(feature = "std")]
+        debug
+            .field("latency_min_ns", &self.latency_min_ns)
+            .field("latency_max_ns", &self.latency_max_ns)
+            .field("latency_p50_ns", &self.latency_p50_ns)
+            .field("latency_p99_ns", &self.latency_p99_ns)
+            .field("mtbf_ns", &self.mtbf_ns)
+            .field("time_window", &self.time_window)
+            .field("per_thread_seed", &self.per_thread_seed);
+        debug
+            .field("scenario_phases", &self.scenario.as_ref().map(|s| s.phases.len()))
+            .field("replay", &self.replay.as_ref().map(Vec::len))
+            .field("sequences", &self.sequences.len())
+            .field("hooks", &self.hook_names())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// Set a custom global failure handler.
+///
+/// You should use `configure_failures()` instead unless you need complete control
+/// over failure behavior.
+pub fn set_global_handler<H: FailureHandler + 'static>(handler: H) {
+    let handler: Box<dyn FailureHandler> = Box::new(handler);
+    let ptr = Box::into_raw(handler);
+
+    let parts: [usize; 2] = unsafe { core::mem::transmute(ptr) };
+
+    GLOBAL_HANDLER_DATA.store(parts[0], Ordering::SeqCst);
+    GLOBAL_HANDLER_VTABLE.store(parts[1], Ordering::SeqCst);
+}
+
+/// Check whether `config` may be installed in this build.
+///
+/// Release builds (no `debug_assertions`) only accept configs built with
+/// `force_in_release(true)`.
+fn activation_allowed(config: &FailureConfig) -> bool {
+    if cfg!(debug_assertions) || config.force_in_release {
+        return true;
+    }
+
+    #[cfg(feature = "std")]
+    std::eprintln!(
+        "fallibles: refusing to install a failure config in a release build \
+         (use FailureConfig::force_in_release(true) to override)"
+    );
+    false
 }
 
 /// Set global configuration.
 ///
 /// This affects all `#[fallible]` functions in your program.
 ///
+/// In release builds the config is ignored unless it was built with
+/// `force_in_release(true)`.
+///
 /// # Example
 /// ```
 /// use fallibles::fallibles_core::{configure_failures, FailureConfig};
@@ -633,203 +4196,922 @@ pub fn set_global_handler<H: FailureHandler + 'static>(handler: H) {
 /// configure_failures(FailureConfig::new().with_probability(0.3));
 /// ```
 pub fn configure_failures(config: FailureConfig) {
+    if !activation_allowed(&config) {
+        return;
+    }
+
     let old_ptr = CONFIG_PTR.swap(Box::into_raw(Box::new(config)) as usize, Ordering::SeqCst);
     if old_ptr != 0 {
         unsafe {
             drop(Box::from_raw(old_ptr as *mut FailureConfig));
         }
     }
-}
+}
+
+/// Clear global configuration.
+///
+/// After calling this, no failures will be injected unless a new config is set.
+pub fn clear_failure_config() {
+    drop(take_failure_config());
+}
+
+/// Install the baseline config used when no thread-local or global config is set.
+///
+/// Lookups go thread-local config, then global config, then this default, so tests
+/// can override a startup chaos level with `with_config()` or `configure_failures()`
+/// and fall back to it afterwards. Clearing the global config doesn't remove the
+/// default; use `clear_default_config()` for that.
+///
+/// Like `configure_failures()`, this does nothing in release builds unless the config
+/// was built with `force_in_release(true)`.
+///
+/// # Example
+/// ```rust,standalone_crate
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// set_default_config(FailureConfig::enable_all());
+/// assert!(should_simulate_failure(fp));
+///
+/// configure_failures(FailureConfig::new());
+/// assert!(!should_simulate_failure(fp));
+///
+/// // Back to the default, not to no injection
+/// clear_failure_config();
+/// assert!(should_simulate_failure(fp));
+///
+/// clear_default_config();
+/// assert!(!should_simulate_failure(fp));
+/// ```
+pub fn set_default_config(config: FailureConfig) {
+    if !activation_allowed(&config) {
+        return;
+    }
+
+    let old_ptr = DEFAULT_CONFIG_PTR.swap(Box::into_raw(Box::new(config)) as usize, Ordering::SeqCst);
+    if old_ptr != 0 {
+        unsafe {
+            drop(Box::from_raw(old_ptr as *mut FailureConfig));
+        }
+    }
+}
+
+/// Remove the config installed with `set_default_config()`.
+pub fn clear_default_config() {
+    let old_ptr = DEFAULT_CONFIG_PTR.swap(0, Ordering::SeqCst);
+    if old_ptr != 0 {
+        unsafe {
+            drop(Box::from_raw(old_ptr as *mut FailureConfig));
+        }
+    }
+}
+
+/// Remove the global configuration and hand it back.
+///
+/// Like `clear_failure_config()`, but the config, counters included, can be put
+/// back later with `configure_failures(*config)`. Returns `None` if no global
+/// config was installed.
+///
+/// # Example
+/// ```rust,standalone_crate
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// configure_failures(FailureConfig::enable_all());
+/// assert!(should_simulate_failure(fp));
+///
+/// // Setup that must not fail
+/// let config = take_failure_config().unwrap();
+/// assert!(!should_simulate_failure(fp));
+/// assert!(take_failure_config().is_none());
+///
+/// configure_failures(*config);
+/// assert!(should_simulate_failure(fp));
+/// assert_eq!(get_failure_stats().unwrap().total_failures, 2);
+/// ```
+pub fn take_failure_config() -> Option<Box<FailureConfig>> {
+    let old_ptr = CONFIG_PTR.swap(0, Ordering::SeqCst);
+    if old_ptr == 0 {
+        return None;
+    }
+    unsafe { Some(Box::from_raw(old_ptr as *mut FailureConfig)) }
+}
+
+/// Turn off failure injection everywhere, without touching any config.
+///
+/// Every `#[fallible]` function on every thread stops failing until
+/// `enable_all_injection()` is called, including those with inline attributes.
+/// Installed configs are kept and resume where they left off.
+///
+/// # Example
+/// ```rust,standalone_crate
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// configure_failures(FailureConfig::enable_all());
+/// assert!(should_simulate_failure(fp));
+///
+/// disable_all();
+/// assert!(!injection_enabled());
+/// assert!(!should_simulate_failure(fp));
+///
+/// enable_all_injection();
+/// assert!(should_simulate_failure(fp));
+/// # clear_failure_config();
+/// ```
+pub fn disable_all() {
+    INJECTION_DISABLED.store(true, Ordering::SeqCst);
+}
+
+/// Turn failure injection back on after `disable_all()`.
+pub fn enable_all_injection() {
+    INJECTION_DISABLED.store(false, Ordering::SeqCst);
+}
+
+/// Check whether failure injection is currently allowed by the kill switch.
+#[inline(always)]
+pub fn injection_enabled() -> bool {
+    !INJECTION_DISABLED.load(Ordering::Relaxed)
+}
+
+/// Set thread-local configuration.
+///
+/// This affects only the current thread, allowing independent failure injection
+/// per thread in concurrent tests. The same release-build guard as
+/// `configure_failures()` applies.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::{configure_thread_failures, FailureConfig};
+/// use std::thread;
+///
+/// thread::spawn(|| {
+///     // This config only affects this thread
+///     configure_thread_failures(FailureConfig::new().with_probability(0.5));
+/// });
+/// ```
+#[cfg(feature = "std")]
+pub fn configure_thread_failures(config: FailureConfig) {
+    if !activation_allowed(&config) {
+        return;
+    }
+
+    let ptr = Box::into_raw(Box::new(config)) as usize;
+    register_thread_stats(ptr);
+    free_thread_config(THREAD_CONFIG_PTR.with(|cell| cell.replace(ptr)));
+}
+
+/// Clear thread-local configuration.
+///
+/// After calling this, the thread will fall back to the global configuration.
+#[cfg(feature = "std")]
+pub fn clear_thread_failure_config() {
+    free_thread_config(THREAD_CONFIG_PTR.with(|cell| cell.replace(0)));
+}
+
+/// Thread-local configs that are still installed, and the summed stats of those
+/// already dropped, for `global_aggregate_stats()`.
+#[cfg(feature = "std")]
+struct ThreadStatsRegistry {
+    live: Vec<usize>,
+    finished: FailureStats,
+}
+
+#[cfg(feature = "std")]
+static THREAD_STATS: std::sync::Mutex<ThreadStatsRegistry> = std::sync::Mutex::new(ThreadStatsRegistry {
+    live: Vec::new(),
+    finished: FailureStats {
+        total_checks: 0,
+        total_failures: 0,
+        limited_failures: 0,
+        suppressed_failures: 0,
+        total_latency_ns: 0,
+    },
+});
+
+/// Track a newly installed thread-local config in `global_aggregate_stats()`.
+#[cfg(feature = "std")]
+fn register_thread_stats(ptr: usize) {
+    let mut registry = THREAD_STATS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    registry.live.push(ptr);
+}
+
+/// Fold a thread-local config's stats into the finished totals, then free it.
+///
+/// The config leaves the registry under its lock, so `global_aggregate_stats()`
+/// never reads one that is being freed.
+#[cfg(feature = "std")]
+fn free_thread_config(ptr: usize) {
+    if ptr == 0 {
+        return;
+    }
+    let config = unsafe { Box::from_raw(ptr as *mut FailureConfig) };
+    let mut registry = THREAD_STATS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(index) = registry.live.iter().position(|live| *live == ptr) {
+        registry.live.swap_remove(index);
+        registry.finished.add(&config.stats());
+    }
+    drop(registry);
+    drop(config);
+}
+
+/// Sum the stats of every thread-local config, installed or already dropped.
+///
+/// `get_failure_stats()` only sees the current thread's config, and a config set
+/// with `with_thread_config()` takes its counts with it when the guard drops. This
+/// keeps them: each thread-local config's stats are folded into a process-wide
+/// total when it is cleared or replaced. Global configs are not included.
+///
+/// # Example
+/// ```rust,standalone_crate
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+/// use std::thread;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// let handles: Vec<_> = (1..=4u64)
+///     .map(|n| {
+///         thread::spawn(move || {
+///             let _guard = with_thread_config(FailureConfig::new().trigger_every(2));
+///             for _ in 0..n * 10 {
+///                 should_simulate_failure(fp);
+///             }
+///             get_failure_stats().unwrap().total_checks
+///         })
+///     })
+///     .collect();
+/// let per_thread: u64 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+///
+/// let _guard = with_thread_config(FailureConfig::enable_all());
+/// should_simulate_failure(fp);
+///
+/// let stats = global_aggregate_stats();
+/// assert_eq!(per_thread, 100);
+/// assert_eq!(stats.total_checks, per_thread + 1);
+/// assert_eq!(stats.total_failures, 50 + 1);
+/// ```
+#[cfg(feature = "std")]
+pub fn global_aggregate_stats() -> FailureStats {
+    let registry = THREAD_STATS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut total = registry.finished.clone();
+    for ptr in &registry.live {
+        total.add(&unsafe { &*(*ptr as *const FailureConfig) }.stats());
+    }
+    total
+}
+
+/// Automatically restores the previous configuration when dropped.
+///
+/// Created by `with_config()` or `with_thread_config()`. Ensures cleanup
+/// even if your code panics. Guards nest: dropping one puts back the config it
+/// replaced, so drop them in the reverse order they were created.
+#[cfg(feature = "std")]
+pub struct FailureConfigGuard {
+    was_global: bool,
+    installed: bool,
+    depth: usize,
+    previous: Option<Box<FailureConfig>>,
+}
+
+#[cfg(feature = "std")]
+impl FailureConfigGuard {
+    /// Get the `config_depth()` right after this guard was created, or 0 if its
+    /// config wasn't installed.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for FailureConfigGuard {
+    fn drop(&mut self) {
+        if !self.installed {
+            return;
+        }
+        let previous = self.previous.take().map_or(0, |config| Box::into_raw(config) as usize);
+        if self.was_global {
+            GLOBAL_GUARD_DEPTH.fetch_sub(1, Ordering::SeqCst);
+            let old_ptr = CONFIG_PTR.swap(previous, Ordering::SeqCst);
+            if old_ptr != 0 {
+                unsafe {
+                    drop(Box::from_raw(old_ptr as *mut FailureConfig));
+                }
+            }
+        } else {
+            THREAD_GUARD_DEPTH.with(|depth| depth.set(depth.get() - 1));
+            free_thread_config(THREAD_CONFIG_PTR.with(|cell| cell.replace(previous)));
+        }
+    }
+}
+
+/// Set global failure configuration with automatic cleanup.
+///
+/// Returns a guard that restores the previous global configuration, if any, when
+/// dropped.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::{with_config, FailureConfig};
+///
+/// {
+///     let _guard = with_config(FailureConfig::new().with_probability(0.3));
+///     // failures enabled here
+/// } // config automatically cleared
+/// ```
+#[cfg(feature = "std")]
+pub fn with_config(config: FailureConfig) -> FailureConfigGuard {
+    if !activation_allowed(&config) {
+        return FailureConfigGuard { was_global: true, installed: false, depth: 0, previous: None };
+    }
+    let previous = take_failure_config();
+    configure_failures(config);
+    GLOBAL_GUARD_DEPTH.fetch_add(1, Ordering::SeqCst);
+    FailureConfigGuard { was_global: true, installed: true, depth: config_depth(), previous }
+}
+
+/// Set thread-local failure configuration with automatic cleanup.
+///
+/// Returns a guard that restores the previous thread-local configuration, if any,
+/// when dropped.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::{with_thread_config, FailureConfig};
+/// use std::thread;
+///
+/// let handle = thread::spawn(|| {
+///     let _guard = with_thread_config(FailureConfig::new().with_probability(0.5));
+///     // only this thread has failures enabled
+/// });
+/// ```
+#[cfg(feature = "std")]
+pub fn with_thread_config(config: FailureConfig) -> FailureConfigGuard {
+    if !activation_allowed(&config) {
+        return FailureConfigGuard { was_global: false, installed: false, depth: 0, previous: None };
+    }
+    let previous = THREAD_CONFIG_PTR.with(|cell| cell.replace(0));
+    let previous = (previous != 0).then(|| unsafe { Box::from_raw(previous as *mut FailureConfig) });
+    configure_thread_failures(config);
+    THREAD_GUARD_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    FailureConfigGuard { was_global: false, installed: true, depth: config_depth(), previous }
+}
+
+/// Run `f` with failure injection suppressed on the current thread.
+///
+/// The inverse of `with_config()`: installs a thread-local config that never fails,
+/// inline attributes included, and restores the previous one when `f` returns or
+/// panics. Useful for teardown code that has to run cleanly. Other threads keep
+/// failing as configured.
+///
+/// # Example
+/// ```
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// let always = InlinePolicy { probability: u32::MAX, ..InlinePolicy::default() };
+/// let _guard = with_thread_config(FailureConfig::new().with_probability(1.0));
+///
+/// let during = without_failures(|| {
+///     (should_simulate_failure(fp), should_simulate_failure_inline(fp, always))
+/// });
+/// assert_eq!(during, (false, false));
+/// assert!(should_simulate_failure(fp));
+/// ```
+#[cfg(feature = "std")]
+pub fn without_failures<R>(f: impl FnOnce() -> R) -> R {
+    let _guard = with_thread_config(FailureConfig::new().when(|| false).force_in_release(true));
+    f()
+}
+
+/// Get how many `with_config()` and `with_thread_config()` guards are live.
+///
+/// Counts global guards from every thread plus the current thread's thread-local
+/// guards. A non-zero depth once a test has finished points at a leaked guard.
+///
+/// # Example
+/// ```rust,standalone_crate
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// assert_eq!(config_depth(), 0);
+///
+/// let outer = with_config(FailureConfig::enable_all());
+/// assert_eq!(outer.depth(), 1);
+/// {
+///     let _inner = with_config(FailureConfig::new());
+///     let local = with_thread_config(FailureConfig::new());
+///     assert_eq!(local.depth(), 3);
+///     assert_eq!(config_depth(), 3);
+///     assert!(!should_simulate_failure(fp));
+/// }
+/// assert_eq!(config_depth(), 1);
+/// assert!(should_simulate_failure(fp));
+///
+/// drop(outer);
+/// assert_eq!(config_depth(), 0);
+/// assert!(!is_active());
+/// ```
+#[cfg(feature = "std")]
+pub fn config_depth() -> usize {
+    GLOBAL_GUARD_DEPTH.load(Ordering::SeqCst) + THREAD_GUARD_DEPTH.with(Cell::get)
+}
+
+/// Check if a failure should be simulated at this point.
+///
+/// This is called internally by the `#[fallible]` macro.
+///
+/// With the `tracing` feature enabled, every check under an active config emits a
+/// `TRACE` event and every injected failure a `WARN` event, both carrying the
+/// `failure_point`, `function`, `file` and `line` fields.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "tracing")]
+/// # {
+/// use fallibles::fallibles_core::*;
+/// use std::sync::{Arc, Mutex};
+/// use tracing::{Event, Id, Level, Metadata, Subscriber, span};
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// #[derive(Clone, Default)]
+/// struct Levels(Arc<Mutex<Vec<Level>>>);
+///
+/// impl Subscriber for Levels {
+///     fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+///     fn new_span(&self, _: &span::Attributes<'_>) -> Id { Id::from_u64(1) }
+///     fn record(&self, _: &Id, _: &span::Record<'_>) {}
+///     fn record_follows_from(&self, _: &Id, _: &Id) {}
+///     fn event(&self, event: &Event<'_>) {
+///         self.0.lock().unwrap().push(*event.metadata().level());
+///     }
+///     fn enter(&self, _: &Id) {}
+///     fn exit(&self, _: &Id) {}
+/// }
+///
+/// let levels = Levels::default();
+/// let _guard = with_thread_config(FailureConfig::new().with_probability(1.0));
+/// tracing::subscriber::with_default(levels.clone(), || should_simulate_failure(fp));
+/// assert_eq!(*levels.0.lock().unwrap(), [Level::TRACE, Level::WARN]);
+/// # }
+/// ```
+///
+/// With the `metrics` feature enabled, the `fallible.checks.total` and
+/// `fallible.failures.total` counters are incremented, labeled with `function`.
+///
+/// ```
+/// # #[cfg(feature = "metrics")]
+/// # {
+/// use fallibles::fallibles_core::*;
+/// use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// let recorder = DebuggingRecorder::new();
+/// let snapshotter = recorder.snapshotter();
+///
+/// let _guard = with_thread_config(FailureConfig::new().trigger_every(2));
+/// metrics::with_local_recorder(&recorder, || {
+///     for _ in 0..4 {
+///         should_simulate_failure(fp);
+///     }
+/// });
+///
+/// let counters: Vec<(String, u64)> = snapshotter
+///     .snapshot()
+///     .into_vec()
+///     .into_iter()
+///     .filter_map(|(key, _, _, value)| match value {
+///         DebugValue::Counter(count) => Some((key.key().name().to_string(), count)),
+///         _ => None,
+///     })
+///     .collect();
+/// assert!(counters.contains(&("fallible.checks.total".to_string(), 4)));
+/// assert!(counters.contains(&("fallible.failures.total".to_string(), 2)));
+/// # }
+/// ```
+#[inline(always)]
+pub fn should_simulate_failure(fp: FailurePoint) -> bool {
+    simulate(fp, None)
+}
+
+/// Check if a failure should be simulated at a point with inline attributes.
+///
+/// This is called internally by the `#[fallible]` macro for `probability`, `ratio`
+/// and `trigger_every` attributes. The point only fails while a config is active,
+/// and that config's kill switch, predicate, seed, limits and callbacks all apply;
+/// only its probability and trigger interval are replaced by `policy`.
+///
+/// # Example
+/// ```
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// let every_other = InlinePolicy { probability: 0, trigger_every: 2, trigger_offset: 0, opt_in: false, args_hash: None };
+///
+/// // No config installed, nothing fails
+/// assert!(!should_simulate_failure_inline(fp, every_other));
+///
+/// let guard = with_thread_config(FailureConfig::new());
+/// assert!(should_simulate_failure_inline(fp, every_other));
+/// assert!(!should_simulate_failure_inline(fp, every_other));
+/// drop(guard);
+/// ```
+#[inline(always)]
+pub fn should_simulate_failure_inline(fp: FailurePoint, policy: InlinePolicy) -> bool {
+    simulate(fp, Some(policy))
+}
+
+fn simulate(fp: FailurePoint, inline: Option<InlinePolicy>) -> bool {
+    if !injection_enabled() {
+        return false;
+    }
 
-/// Clear global configuration.
-///
-/// After calling this, no failures will be injected unless a new config is set.
-pub fn clear_failure_config() {
-    let old_ptr = CONFIG_PTR.swap(0, Ordering::SeqCst);
-    if old_ptr != 0 {
-        unsafe {
-            drop(Box::from_raw(old_ptr as *mut FailureConfig));
-        }
+    CALL_COUNTS.increment(fp.id);
+    let fired = with_active_config(|config| check_and_trigger(config, fp, inline)).unwrap_or(false);
+
+    #[cfg(feature = "std")]
+    if fired {
+        clear_fired_one_shot();
     }
+    fired
 }
 
-/// Set thread-local configuration.
+/// Make the next check on this thread fail, exactly once.
 ///
-/// This affects only the current thread, allowing independent failure injection
-/// per thread in concurrent tests.
+/// Installs a thread-local `FailureConfig::once()` that removes itself after firing,
+/// so the thread falls back to the global config afterwards. Replaces any thread-local
+/// config already installed.
 ///
 /// # Example
-/// ```
-/// use fallibles::fallibles_core::{configure_thread_failures, FailureConfig};
-/// use std::thread;
+/// ```rust,standalone_crate
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
 ///
-/// thread::spawn(|| {
-///     // This config only affects this thread
-///     configure_thread_failures(FailureConfig::new().with_probability(0.5));
-/// });
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// fail_next();
+/// assert!(is_thread_active());
+/// assert!(should_simulate_failure(fp));
+///
+/// assert!(!is_thread_active());
+/// assert!(!should_simulate_failure(fp));
+/// assert!(!should_simulate_failure(fp));
 /// ```
 #[cfg(feature = "std")]
-pub fn configure_thread_failures(config: FailureConfig) {
-    THREAD_CONFIG_PTR.with(|cell| {
-        let old_ptr = cell.replace(Box::into_raw(Box::new(config)) as usize);
-        if old_ptr != 0 {
-            unsafe {
-                drop(Box::from_raw(old_ptr as *mut FailureConfig));
-            }
-        }
+pub fn fail_next() {
+    configure_thread_failures(FailureConfig {
+        one_shot: true,
+        ..FailureConfig::once()
     });
 }
 
-/// Clear thread-local configuration.
+/// Remove the thread-local config if it was installed by `fail_next()`.
 ///
-/// After calling this, the thread will fall back to the global configuration.
+/// Only called once the active config stopped being borrowed.
 #[cfg(feature = "std")]
-pub fn clear_thread_failure_config() {
-    THREAD_CONFIG_PTR.with(|cell| {
-        let old_ptr = cell.replace(0);
-        if old_ptr != 0 {
-            unsafe {
-                drop(Box::from_raw(old_ptr as *mut FailureConfig));
-            }
-        }
+fn clear_fired_one_shot() {
+    let one_shot = THREAD_CONFIG_PTR.with(|cell| {
+        let ptr = *cell.borrow();
+        ptr != 0 && unsafe { (*(ptr as *const FailureConfig)).one_shot }
     });
+    if one_shot {
+        clear_thread_failure_config();
+    }
 }
 
-/// Automatically clears configuration when dropped.
+/// Check if a failure should be simulated at this point, from async code.
 ///
-/// Created by `with_config()` or `with_thread_config()`. Ensures cleanup
-/// even if your code panics.
-#[cfg(feature = "std")]
-pub struct FailureConfigGuard {
-    was_global: bool,
+/// This is called internally by the `#[fallible]` macro for `async fn`. It behaves
+/// like `should_simulate_failure()`, except that injected latency is awaited with
+/// `async_delay()` instead of blocking the thread.
+pub async fn should_simulate_failure_async(fp: FailurePoint) -> bool {
+    simulate_async(fp, None).await
 }
 
-#[cfg(feature = "std")]
-impl Drop for FailureConfigGuard {
-    fn drop(&mut self) {
-        if self.was_global {
-            clear_failure_config();
-        } else {
-            clear_thread_failure_config();
-        }
+/// Check if a failure should be simulated at a point with inline attributes, from async code.
+///
+/// The async counterpart of `should_simulate_failure_inline()`.
+pub async fn should_simulate_failure_inline_async(fp: FailurePoint, policy: InlinePolicy) -> bool {
+    simulate_async(fp, Some(policy)).await
+}
+
+async fn simulate_async(fp: FailurePoint, inline: Option<InlinePolicy>) -> bool {
+    if !injection_enabled() {
+        return false;
+    }
+
+    CALL_COUNTS.increment(fp.id);
+
+    #[cfg(feature = "std")]
+    if let Some(latency) = with_active_config(|config| injected_latency(config, fp)).flatten() {
+        async_delay(latency).await;
+    }
+
+    let fired = with_active_config(|config| trigger_decision(config, fp, inline)).unwrap_or(false);
+
+    #[cfg(feature = "std")]
+    if fired {
+        clear_fired_one_shot();
     }
+    fired
 }
 
-/// Set global failure configuration with automatic cleanup.
+/// Wait for an injected delay in async code.
 ///
-/// Returns a guard that clears the configuration when dropped.
+/// With the `tokio` feature enabled this is `tokio::time::sleep`, so delays cooperate
+/// with the runtime and with `tokio::time::pause()`. Otherwise it falls back to
+/// blocking the current thread.
 ///
 /// # Example
 /// ```
-/// use fallibles::fallibles_core::{with_config, FailureConfig};
-///
-/// {
-///     let _guard = with_config(FailureConfig::new().with_probability(0.3));
-///     // failures enabled here
-/// } // config automatically cleared
-/// ```
-#[cfg(feature = "std")]
-pub fn with_config(config: FailureConfig) -> FailureConfigGuard {
-    configure_failures(config);
-    FailureConfigGuard { was_global: true }
-}
-
-/// Set thread-local failure configuration with automatic cleanup.
+/// # #[cfg(feature = "tokio")]
+/// # {
+/// use fallibles::fallibles_core::*;
+/// use std::time::Duration;
 ///
-/// Returns a guard that clears the configuration when dropped.
+/// # let fp = FailurePoint::new(FailurePointId(1), "fetch");
+/// # tokio::runtime::Builder::new_current_thread()
+/// #     .enable_time()
+/// #     .start_paused(true)
+/// #     .build()
+/// #     .unwrap()
+/// #     .block_on(async {
+/// let delay = Duration::from_secs(5);
+/// let _guard = with_thread_config(FailureConfig::new().with_latency(delay, delay));
 ///
-/// # Example
-/// ```
-/// use fallibles::fallibles_core::{with_thread_config, FailureConfig};
-/// use std::thread;
+/// let virtual_start = tokio::time::Instant::now();
+/// let real_start = std::time::Instant::now();
+/// should_simulate_failure_async(fp).await;
 ///
-/// let handle = thread::spawn(|| {
-///     let _guard = with_thread_config(FailureConfig::new().with_probability(0.5));
-///     // only this thread has failures enabled
-/// });
+/// // the clock is paused, so only virtual time advanced
+/// assert!(virtual_start.elapsed() >= delay);
+/// assert!(real_start.elapsed() < delay);
+/// # });
+/// # }
 /// ```
 #[cfg(feature = "std")]
-pub fn with_thread_config(config: FailureConfig) -> FailureConfigGuard {
-    configure_thread_failures(config);
-    FailureConfigGuard { was_global: false }
+pub async fn async_delay(duration: Duration) {
+    #[cfg(feature = "tokio")]
+    tokio::time::sleep(duration).await;
+
+    #[cfg(not(feature = "tokio"))]
+    std::thread::sleep(duration);
 }
 
-/// Check if a failure should be simulated at this point.
-///
-/// This is called internally by the `#[fallible]` macro.
-#[inline(always)]
-pub fn should_simulate_failure(fp: FailurePoint) -> bool {
-    #[cfg(feature = "std")]
-    {
-        let thread_ptr = THREAD_CONFIG_PTR.with(|cell| *cell.borrow());
-        if thread_ptr != 0 {
-            return unsafe {
-                let config = &*(thread_ptr as *const FailureConfig);
-                check_and_trigger(config, fp)
-            };
-        }
+/// Pick the latency to inject for this check, if any, and add it to the stats.
+#[cfg(feature = "std")]
+fn injected_latency(config: &FailureConfig, fp: FailurePoint) -> Option<Duration> {
+    if config.latency_max_ns == 0 && config.latency_p99_ns == 0 {
+        return None;
     }
 
-    let config_ptr = CONFIG_PTR.load(Ordering::Acquire);
-    if config_ptr == 0 {
-        return false;
+    let latency_ns = if config.latency_p99_ns > 0 {
+        config.profile_latency_ns(fp)
+    } else if config.latency_min_ns == config.latency_max_ns {
+        config.latency_min_ns
+    } else {
+        // Generate random latency in range [min, max]
+        let counter = config.counter.load(Ordering::Relaxed);
+        let mut bytes = [0u8; 12];
+        bytes[0..4].copy_from_slice(&fp.id.0.to_le_bytes());
+        bytes[4..12].copy_from_slice(&counter.to_le_bytes());
+        let hash = fxhash::hash64(&bytes);
+
+        let range = config.latency_max_ns - config.latency_min_ns;
+        config.latency_min_ns + (hash % range)
+    };
+
+    if latency_ns == 0 {
+        return None;
     }
 
-    unsafe {
-        let config = &*(config_ptr as *const FailureConfig);
-        check_and_trigger(config, fp)
+    config.total_latency_ns.fetch_add(latency_ns, Ordering::Relaxed);
+    let latency = Duration::from_nanos(latency_ns);
+    if let Some(on_failure_kind) = &config.on_failure_kind {
+        on_failure_kind(fp, FailureKind::Delay(latency));
     }
+    Some(latency)
 }
 
-fn check_and_trigger(config: &FailureConfig, fp: FailurePoint) -> bool {
+fn check_and_trigger(config: &FailureConfig, fp: FailurePoint, inline: Option<InlinePolicy>) -> bool {
     // Inject latency if configured
     #[cfg(feature = "std")]
-    if config.latency_max_ns > 0 {
-        let latency_ns = if config.latency_min_ns == config.latency_max_ns {
-            config.latency_min_ns
-        } else {
-            // Generate random latency in range [min, max]
-            let counter = config.counter.load(Ordering::Relaxed);
-            let mut bytes = [0u8; 12];
-            bytes[0..4].copy_from_slice(&fp.id.0.to_le_bytes());
-            bytes[4..12].copy_from_slice(&counter.to_le_bytes());
-            let hash = fxhash::hash64(&bytes);
-            
-            let range = config.latency_max_ns - config.latency_min_ns;
-            config.latency_min_ns + (hash % range)
-        };
-        
-        if latency_ns > 0 {
-            std::thread::sleep(Duration::from_nanos(latency_ns));
-            config.total_latency_ns.fetch_add(latency_ns, Ordering::Relaxed);
-        }
+    if let Some(latency) = injected_latency(config, fp) {
+        std::thread::sleep(latency);
     }
 
+    trigger_decision(config, fp, inline)
+}
+
+fn trigger_decision(config: &FailureConfig, fp: FailurePoint, inline: Option<InlinePolicy>) -> bool {
+    let _scope = PointScope::enter(fp);
+    config.checks_seen.fetch_add(1, Ordering::Relaxed);
+    #[cfg(feature = "std")]
+    REQUESTED_VARIANT.with(|cell| cell.set(None));
+
     if let Some(on_check) = &config.on_check {
         on_check(fp);
     }
 
-    let should_fail = config.should_trigger(fp.id);
+    #[cfg(feature = "tracing")]
+    tracing::event!(
+        tracing::Level::TRACE,
+        failure_point = fp.id.0,
+        function = fp.function,
+        file = fp.file,
+        line = fp.line,
+        "checked failure point"
+    );
 
-    if should_fail {
-        // Check if we've hit the failure limit
-        if config.max_failures > 0 {
-            let current_failures = config.failures_triggered.load(Ordering::Relaxed);
-            if current_failures >= config.max_failures {
-                // Record that we would have failed but were limited
-                config.limited_failures.fetch_add(1, Ordering::Relaxed);
-                return false;
-            }
+    #[cfg(feature = "metrics")]
+    metrics::counter!("fallible.checks.total", "function" => fp.function).increment(1);
+
+    let mut should_fail = config.should_trigger(fp, inline);
+
+    if config.strict && !should_fail && config.must_fail(fp, inline) {
+        panic!(
+            "strict mode: `{}` at {}:{}:{} passed a check that should always fail ({})",
+            fp.function,
+            fp.file,
+            fp.line,
+            fp.column,
+            config.describe()
+        );
+    }
+
+    if should_fail
+        && let Some(filter) = &config.failure_filter
+        && !filter(fp)
+    {
+        config.suppressed_failures.fetch_add(1, Ordering::Relaxed);
+        should_fail = false;
+    }
+
+    // Check if we've hit the failure limit
+    if should_fail && config.max_failures > 0 {
+        let current_failures = config.failures_triggered.load(Ordering::Relaxed);
+        if current_failures >= config.max_failures {
+            // Record that we would have failed but were limited
+            config.limited_failures.fetch_add(1, Ordering::Relaxed);
+            should_fail = false;
         }
+    }
+
+    #[cfg(feature = "std")]
+    if let Some(recorded) = &config.recorded
+        && let Ok(mut decisions) = recorded.lock()
+    {
+        decisions.push(should_fail);
+    }
+
+    if !should_fail {
+        config.note_passed(fp.id);
+        #[cfg(feature = "std")]
+        REQUESTED_VARIANT.with(|cell| cell.set(None));
+    }
 
-        config.failures_triggered.fetch_add(1, Ordering::Relaxed);
+    if should_fail {
+        let failures = config.failures_triggered.fetch_add(1, Ordering::Relaxed) + 1;
+        #[cfg(feature = "std")]
+        if let Some(gaps) = &config.gaps
+            && let Ok(mut gaps) = gaps.lock()
+        {
+            gaps.record(config.checks_seen.load(Ordering::Relaxed));
+        }
         if let Some(on_failure) = &config.on_failure {
             on_failure(fp);
         }
+        if let Some(on_failure_kind) = &config.on_failure_kind {
+            on_failure_kind(fp, FailureKind::Error);
+        }
+        for (n, callback) in &config.on_nth_failure {
+            if *n == failures {
+                callback(fp);
+            }
+        }
+
+        #[cfg(feature = "json")]
+        if let Some(json_log) = &config.json_log
+            && let Ok(mut writer) = json_log.lock()
+            && let Ok(mut line) = serde_json::to_vec(&FailureEvent::now(fp))
+        {
+            line.push(b'\n');
+            let _ = writer.write_all(&line);
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::event!(
+            tracing::Level::WARN,
+            failure_point = fp.id.0,
+            function = fp.function,
+            file = fp.file,
+            line = fp.line,
+            "injected failure"
+        );
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("fallible.failures.total", "function" => fp.function).increment(1);
+    }
+
+    should_fail
+}
+
+/// Run `f` against the active configuration.
+///
+/// Checks thread-local config first, then falls back to global config.
+/// Returns `None` if no configuration is active.
+fn with_active_config<R>(f: impl FnOnce(&FailureConfig) -> R) -> Option<R> {
+    #[cfg(feature = "std")]
+    {
+        let thread_ptr = THREAD_CONFIG_PTR.with(|cell| *cell.borrow());
+        if thread_ptr != 0 {
+            return unsafe { Some(f(&*(thread_ptr as *const FailureConfig))) };
+        }
+    }
+
+    let mut config_ptr = CONFIG_PTR.load(Ordering::Acquire);
+    if config_ptr == 0 {
+        config_ptr = DEFAULT_CONFIG_PTR.load(Ordering::Acquire);
+    }
+    if config_ptr == 0 {
+        return None;
+    }
+
+    unsafe { Some(f(&*(config_ptr as *const FailureConfig))) }
+}
+
+/// Check whether any failure config is installed for the current thread.
+///
+/// True when a thread-local, global or default config is active.
+///
+/// # Example
+/// ```rust,standalone_crate
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// assert!(!is_active());
+///
+/// let guard = with_config(FailureConfig::new());
+/// assert!(is_active());
+/// drop(guard);
+/// assert!(!is_active());
+///
+/// let guard = with_thread_config(FailureConfig::new());
+/// assert!(is_active());
+/// drop(guard);
+/// assert!(!is_active());
+/// ```
+pub fn is_active() -> bool {
+    #[cfg(feature = "std")]
+    if is_thread_active() {
         return true;
     }
 
-    false
+    is_global_active() || DEFAULT_CONFIG_PTR.load(Ordering::Acquire) != 0
+}
+
+/// Check whether a global failure config is installed.
+///
+/// The default config from `set_default_config()` doesn't count.
+///
+/// # Example
+/// ```rust,standalone_crate
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// configure_failures(FailureConfig::new());
+/// assert!(is_global_active());
+/// assert!(!is_thread_active());
+///
+/// clear_failure_config();
+/// assert!(!is_global_active());
+/// ```
+pub fn is_global_active() -> bool {
+    CONFIG_PTR.load(Ordering::Acquire) != 0
+}
+
+/// Check whether a thread-local failure config is installed on the current thread.
+///
+/// # Example
+/// ```
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// assert!(!is_thread_active());
+/// {
+///     let _guard = with_thread_config(FailureConfig::new());
+///     assert!(is_thread_active());
+///     assert!(!std::thread::spawn(is_thread_active).join().unwrap());
+/// }
+/// assert!(!is_thread_active());
+/// ```
+#[cfg(feature = "std")]
+pub fn is_thread_active() -> bool {
+    THREAD_CONFIG_PTR.with(|cell| *cell.borrow() != 0)
 }
 
 /// Get statistics about the current configuration.
@@ -848,24 +5130,184 @@ fn check_and_trigger(config: &FailureConfig, fp: FailurePoint) -> bool {
 /// }
 /// ```
 pub fn get_failure_stats() -> Option<FailureStats> {
-    #[cfg(feature = "std")]
-    {
-        let thread_ptr = THREAD_CONFIG_PTR.with(|cell| *cell.borrow());
-        if thread_ptr != 0 {
-            return unsafe {
-                let config = &*(thread_ptr as *const FailureConfig);
-                Some(config.stats())
-            };
-        }
-    }
+    with_active_config(FailureConfig::stats)
+}
 
-    let config_ptr = CONFIG_PTR.load(Ordering::Acquire);
-    if config_ptr == 0 {
-        return None;
+/// Run `f` `n` times under `config` and return the config's stats.
+///
+/// The config is installed for the current thread only, and whatever was active
+/// before is restored afterwards, even if `f` panics. Results of `f` are
+/// discarded; the stats count the checks and failures of every point `f` reached.
+///
+/// # Example
+/// ```
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// let query = || if should_simulate_failure(fp) { Err("injected") } else { Ok(()) };
+///
+/// let stats = sample(FailureConfig::new().trigger_every(4), 100, query);
+/// assert_eq!(stats.total_checks, 100);
+/// assert_eq!(stats.total_failures, 25);
+/// assert!(!is_active());
+/// ```
+#[cfg(feature = "std")]
+pub fn sample<T, E>(config: FailureConfig, n: usize, mut f: impl FnMut() -> Result<T, E>) -> FailureStats {
+    let guard = with_thread_config(config);
+    for _ in 0..n {
+        let _ = f();
     }
+    guard
+        .installed
+        .then(get_failure_stats)
+        .flatten()
+        .unwrap_or_else(|| FailureConfig::new().stats())
+}
+
+/// Get the number of times each failure point has been checked.
+///
+/// Unlike `FailureStats`, these counts live outside any configuration, so they keep
+/// accumulating across `configure_failures()` calls and even while no config is active.
+/// Up to `GLOBAL_REGISTRY_CAPACITY` distinct points are tracked; points beyond
+/// that aren't counted and never appear here.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(7), "db_query");
+/// let calls = |id| {
+///     global_call_counts()
+///         .into_iter()
+///         .find(|(point, _)| *point == id)
+///         .map_or(0, |(_, count)| count)
+/// };
+///
+/// configure_thread_failures(FailureConfig::new());
+/// should_simulate_failure(fp);
+/// configure_thread_failures(FailureConfig::new().with_probability(0.5));
+/// should_simulate_failure(fp);
+/// should_simulate_failure(fp);
+///
+/// assert_eq!(calls(fp.id), 3);
+/// ```
+pub fn global_call_counts() -> Vec<(FailurePointId, u64)> {
+    CALL_COUNTS.entries()
+}
+
+/// Get the number of times the failure point `id` has been checked.
+///
+/// Reads the same registry as `global_call_counts()`, which counts points with inline
+/// attributes like any other. Returns 0 for points that were never checked
+/// or that found no free slot in the registry.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(8), "db_query");
+/// let every_third = InlinePolicy { probability: 0, trigger_every: 3, trigger_offset: 0, opt_in: false, args_hash: None };
+/// for _ in 0..4 {
+///     should_simulate_failure_inline(fp, every_third);
+/// }
+/// assert_eq!(call_count(fp.id), 4);
+/// assert_eq!(call_count(FailurePointId(9)), 0);
+/// ```
+pub fn call_count(id: FailurePointId) -> u64 {
+    CALL_COUNTS.get(id)
+}
+
+/// Reset every count in the global call registry to zero.
+///
+/// # Example
+/// ```rust,standalone_crate
+/// use fallibles::fallibles_core::*;
+///
+/// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+/// should_simulate_failure(fp);
+/// assert_eq!(call_count(fp.id), 1);
+///
+/// reset_call_counts();
+/// assert_eq!(call_count(fp.id), 0);
+/// ```
+pub fn reset_call_counts() {
+    CALL_COUNTS.reset();
+}
+
+/// Get the gaps between failures counted by the current configuration.
+///
+/// Returns `None` if no configuration is active. See `FailureConfig::gap_histogram()`.
+#[cfg(feature = "std")]
+pub fn get_gap_histogram() -> Option<Vec<u64>> {
+    with_active_config(FailureConfig::gap_histogram)
+}
+
+/// Get the trigger decisions recorded by the current configuration.
+///
+/// Returns `None` if no configuration is active. The list is empty unless
+/// the configuration was built with `record()`.
+#[cfg(feature = "std")]
+pub fn get_recorded_decisions() -> Option<Vec<bool>> {
+    with_active_config(FailureConfig::recorded_decisions)
+}
+
+/// The items most setups need, without glob-importing the whole crate.
+///
+/// # Example
+/// ```
+/// use fallibles::fallibles_core::prelude::*;
+///
+/// let _guard = with_config(FailureConfig::new().with_probability(0.3));
+/// ```
+pub mod prelude {
+    #[cfg(feature = "std")]
+    pub use crate::with_config;
+    pub use crate::{FailureConfig, FailurePoint, FallibleError, configure_failures};
+}
+
+/// Helpers for asserting on failure behavior in tests.
+#[cfg(feature = "std")]
+pub mod testing {
+    /// Assert that `f` reports failure at `expected` rate, within `tolerance`.
+    ///
+    /// Calls `f` `samples` times, counting `true` as a failure, and panics with the
+    /// observed rate if it falls outside `expected ± tolerance`. Rates are fractions
+    /// from 0.0 to 1.0.
+    ///
+    /// # Panics
+    /// Panics if `samples` is zero or the observed rate is out of range.
+    ///
+    /// # Example
+    /// ```
+    /// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+    /// use fallibles::fallibles_core::testing::assert_rate_within;
+    /// use fallibles::fallibles_core::*;
+    ///
+    /// # let fp = FailurePoint::new(FailurePointId(1), "db_query");
+    /// let _guard = with_thread_config(FailureConfig::new().with_probability(0.5).with_seed(7));
+    /// assert_rate_within(0.5, 0.02, 20_000, || should_simulate_failure(fp));
+    /// ```
+    ///
+    /// A biased source is rejected:
+    /// ```should_panic
+    /// use fallibles::fallibles_core::testing::assert_rate_within;
+    ///
+    /// let mut calls = 0;
+    /// assert_rate_within(0.5, 0.05, 1_000, || {
+    ///     calls += 1;
+    ///     calls % 4 == 0
+    /// });
+    /// ```
+    #[track_caller]
+    pub fn assert_rate_within(expected: f64, tolerance: f64, samples: usize, mut f: impl FnMut() -> bool) {
+        assert!(samples > 0, "assert_rate_within needs at least one sample");
 
-    unsafe {
-        let config = &*(config_ptr as *const FailureConfig);
-        Some(config.stats())
+        let failures = (0..samples).filter(|_| f()).count();
+        let observed = failures as f64 / samples as f64;
+        assert!(
+            (observed - expected).abs() <= tolerance,
+            "observed failure rate {observed:.4} ({failures}/{samples}) is outside {expected} ± {tolerance}"
+        );
     }
 }
@@ -8,36 +8,601 @@
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
-    Data, DeriveInput, Fields, GenericArgument, Ident, ItemFn, Lit, LitBool, LitFloat, LitInt,
-    Meta, PathArguments, ReturnType, Token, Type, parse::Parse, parse_macro_input,
+    Data, DeriveInput, ExprClosure, FnArg, Fields, GenericArgument, Ident, ItemFn, LitBool, LitInt,
+    LitStr, Meta, Pat, Path, PathArguments, ReturnType, Stmt, Token, TraitItemFn, Type, TypeParamBound, ext::IdentExt, parse::Parse,
+    parse_macro_input, punctuated::Punctuated, spanned::Spanned,
 };
 
-fn extract_result_error_type(return_type: &ReturnType) -> Option<&Type> {
-    if let ReturnType::Type(_, ty) = return_type
-        && let Type::Path(type_path) = &**ty
+/// How a `#[fallible]` function hands back its `Result`, carrying the `T` and `E` types.
+///
+/// `ControlFlow<B, C>` counts as a `Result<C, B>` that fails by breaking.
+enum ReturnShape {
+    /// `Result<T, E>`, directly or from an `async fn`
+    Result(ResultTypes),
+    /// `impl Future<Output = Result<T, E>>`
+    ImplFuture(ResultTypes),
+    /// `Pin<Box<dyn Future<Output = Result<T, E>>>>`
+    BoxedFuture(ResultTypes),
+}
+
+#[derive(Clone)]
+struct ResultTypes {
+    ok: Type,
+    err: Type,
+    /// Whether this is a `ControlFlow`, built with `Continue`/`Break` instead of `Ok`/`Err`
+    control_flow: bool,
+}
+
+impl ReturnShape {
+    fn types(&self) -> ResultTypes {
+        match self {
+            ReturnShape::Result(types) | ReturnShape::ImplFuture(types) | ReturnShape::BoxedFuture(types) => {
+                types.clone()
+            }
+        }
+    }
+}
+
+/// Get the `T` and `E` of a `Result<T, E>`, or the `C` and `B` of a `ControlFlow<B, C>`.
+///
+/// A single-argument `Result<T>` alias uses the `error = ..` attribute as `E`, or
+/// the error type of `anyhow::Result` and `eyre::Result`.
+fn result_types(ty: &Type, error: Option<&Type>) -> Option<ResultTypes> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segments = &type_path.path.segments;
+    let segment = segments.last()?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    if segment.ident == "ControlFlow" {
+        return match (args.args.first(), args.args.iter().nth(1), args.args.len()) {
+            (Some(GenericArgument::Type(brk)), cont, 1 | 2) => Some(ResultTypes {
+                ok: match cont {
+                    Some(GenericArgument::Type(cont)) => cont.clone(),
+                    Some(_) => return None,
+                    None => syn::parse_quote! { () },
+                },
+                err: brk.clone(),
+                control_flow: true,
+            }),
+            _ => None,
+        };
+    }
+    if segment.ident != "Result" {
+        return None;
+    }
+    match (args.args.first(), args.args.iter().nth(1), args.args.len()) {
+        (Some(GenericArgument::Type(ok)), Some(GenericArgument::Type(err)), 2) => Some(ResultTypes {
+            ok: ok.clone(),
+            err: err.clone(),
+            control_flow: false,
+        }),
+        (Some(GenericArgument::Type(ok)), None, 1) => {
+            let err = match error {
+                Some(error) => error.clone(),
+                None => match segments.iter().rev().nth(1)?.ident.to_string().as_str() {
+                    "anyhow" => syn::parse_quote! { ::anyhow::Error },
+                    "eyre" => syn::parse_quote! { ::eyre::Report },
+                    _ => return None,
+                },
+            };
+            Some(ResultTypes { ok: ok.clone(), err, control_flow: false })
+        }
+        _ => None,
+    }
+}
+
+/// Find the `Output` type of a `Future<Output = ..>` bound.
+fn future_output(bounds: &Punctuated<TypeParamBound, Token![+]>) -> Option<&Type> {
+    bounds.iter().find_map(|bound| {
+        if let TypeParamBound::Trait(trait_bound) = bound
+            && let Some(segment) = trait_bound.path.segments.last()
+            && segment.ident == "Future"
+            && let PathArguments::AngleBracketed(args) = &segment.arguments
+        {
+            args.args.iter().find_map(|arg| match arg {
+                GenericArgument::AssocType(assoc) if assoc.ident == "Output" => Some(&assoc.ty),
+                _ => None,
+            })
+        } else {
+            None
+        }
+    })
+}
+
+/// Get the only type argument of a path type named `name`, e.g. `Box<T>`.
+fn single_type_arg<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    if let Type::Path(type_path) = ty
         && let Some(segment) = type_path.path.segments.last()
-        && segment.ident == "Result"
+        && segment.ident == name
         && let PathArguments::AngleBracketed(args) = &segment.arguments
-        && args.args.len() == 2
-        && let Some(GenericArgument::Type(err_type)) = args.args.iter().nth(1)
+        && args.args.len() == 1
+        && let Some(GenericArgument::Type(inner)) = args.args.first()
     {
-        return Some(err_type);
+        return Some(inner);
     }
     None
 }
 
+fn return_shape(return_type: &ReturnType, error: Option<&Type>) -> Option<ReturnShape> {
+    let ReturnType::Type(_, ty) = return_type else {
+        return None;
+    };
+
+    if let Some(types) = result_types(ty, error) {
+        return Some(ReturnShape::Result(types));
+    }
+
+    if let Type::ImplTrait(impl_trait) = &**ty {
+        return future_output(&impl_trait.bounds)
+            .and_then(|output| result_types(output, error))
+            .map(ReturnShape::ImplFuture);
+    }
+
+    if let Some(boxed) = single_type_arg(ty, "Pin").and_then(|pinned| single_type_arg(pinned, "Box"))
+        && let Type::TraitObject(trait_object) = boxed
+    {
+        return future_output(&trait_object.bounds)
+            .and_then(|output| result_types(output, error))
+            .map(ReturnShape::BoxedFuture);
+    }
+
+    None
+}
+
+fn pat_ident(pat: &Pat) -> Option<&Ident> {
+    match pat {
+        Pat::Ident(pat_ident) => Some(&pat_ident.ident),
+        Pat::Type(pat_type) => pat_ident(&pat_type.pat),
+        _ => None,
+    }
+}
+
+/// Check whether `tokens` use any of `names` as an identifier.
+fn mentions_any(tokens: proc_macro2::TokenStream, names: &[Ident]) -> bool {
+    tokens.into_iter().any(|tree| match tree {
+        proc_macro2::TokenTree::Ident(ident) => names.contains(&ident),
+        proc_macro2::TokenTree::Group(group) => mentions_any(group.stream(), names),
+        _ => false,
+    })
+}
+
+/// Add a `ty: bound` predicate when `ty` depends on the function's type parameters
+/// or `Self`, e.g. `T::Err: FallibleError` for `Result<T, T::Err>`.
+///
+/// Concrete types are left alone so a missing impl is reported on the type itself.
+fn bound_generic_type(sig: &syn::Signature, ty: &Type, bound: proc_macro2::TokenStream) -> syn::Signature {
+    let mut names: Vec<Ident> = sig.generics.type_params().map(|param| param.ident.clone()).collect();
+    names.push(Ident::new("Self", proc_macro2::Span::call_site()));
+
+    let mut sig = sig.clone();
+    if mentions_any(quote! { #ty }, &names) {
+        sig.generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse_quote! { #ty: #bound });
+    }
+    sig
+}
+
+/// Build the call to a `when = |..| ..` closure, passing the function arguments
+/// it names by reference.
+///
+/// Untyped closure parameters are annotated with `&T` from the matching argument,
+/// since an immediately-called closure can't infer them.
+fn when_call(closure: &ExprClosure, sig: &syn::Signature) -> syn::Result<proc_macro2::TokenStream> {
+    let fn_args: Vec<(&Ident, &Type)> = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(pat_type) => pat_ident(&pat_type.pat).map(|ident| (ident, &*pat_type.ty)),
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+
+    let mut closure = closure.clone();
+    let mut call_args = Vec::new();
+    for input in closure.inputs.iter_mut() {
+        let ident = pat_ident(input).cloned().ok_or_else(|| {
+            syn::Error::new_spanned(&*input, "`when` parameters must be plain argument names")
+        })?;
+        let Some((_, ty)) = fn_args.iter().find(|(arg, _)| **arg == ident) else {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!("`{}` is not an argument of this function", ident),
+            ));
+        };
+        if let Pat::Ident(_) = input {
+            *input = Pat::Type(syn::PatType {
+                attrs: Vec::new(),
+                pat: Box::new(input.clone()),
+                colon_token: Default::default(),
+                ty: syn::parse_quote! { &#ty },
+            });
+        }
+        call_args.push(ident);
+    }
+
+    Ok(quote! { (#closure)(#(&#call_args),*) })
+}
+
+/// Build an expression hashing the function's arguments, other than `self`, for `by_args`.
+fn args_hash(sig: &syn::Signature, krate: &proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let mut args = Vec::new();
+    for arg in &sig.inputs {
+        if let FnArg::Typed(pat_type) = arg {
+            let ident = pat_ident(&pat_type.pat).ok_or_else(|| {
+                syn::Error::new_spanned(&pat_type.pat, "`by_args` needs plain argument names to hash")
+            })?;
+            args.push(ident);
+        }
+    }
+
+    Ok(quote! {
+        {
+            let mut hasher = <#krate::__private::fxhash::FxHasher64 as ::core::default::Default>::default();
+            #(::core::hash::Hash::hash(&#args, &mut hasher);)*
+            ::core::hash::Hasher::finish(&hasher)
+        }
+    })
+}
+
+/// Insert `check` before the final expression or `return` of `block`.
+///
+/// Without either, the check goes after the last statement.
+fn check_at_end(block: &syn::Block, check: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let mut stmts = block.stmts.clone();
+    let tail = match stmts.last() {
+        Some(Stmt::Expr(_, None)) | Some(Stmt::Expr(syn::Expr::Return(_), _)) => stmts.pop(),
+        _ => None,
+    };
+    quote! {
+        {
+            #(#stmts)*
+            #check
+            #tail
+        }
+    }
+}
+
+/// The path generated code uses to reach `fallibles-core`.
+fn crate_path(krate: Option<&Path>) -> proc_macro2::TokenStream {
+    match krate {
+        Some(path) => quote! { #path },
+        None => quote! { ::fallibles::fallibles_core },
+    }
+}
+
+/// Find the field a derived `simulated_failure()` puts its message in.
+///
+/// That's `message_field` when given (a name, or an index for tuple fields),
+/// otherwise a field named `message` or the only tuple field.
+fn message_member(fields: &Fields, message_field: Option<&str>) -> Option<syn::Member> {
+    match fields {
+        Fields::Named(fields) => {
+            let target = message_field.unwrap_or("message");
+            let ident = fields.named.iter().filter_map(|f| f.ident.as_ref()).find(|i| *i == target)?;
+            Some(syn::Member::Named(ident.clone()))
+        }
+        Fields::Unnamed(fields) => {
+            let index = match message_field {
+                Some(field) => field.parse::<usize>().ok()?,
+                None if fields.unnamed.len() == 1 => 0,
+                None => return None,
+            };
+            (index < fields.unnamed.len()).then(|| syn::Member::Unnamed(index.into()))
+        }
+        Fields::Unit => None,
+    }
+}
+
+/// The type parameters of a derived type and the bounds its generated impl needs.
+struct GenericFields<'a> {
+    krate: &'a proc_macro2::TokenStream,
+    params: Vec<Ident>,
+}
+
+impl GenericFields<'_> {
+    /// Check whether `ty` depends on the type parameters.
+    fn is_generic(&self, ty: &Type) -> bool {
+        mentions_any(quote! { #ty }, &self.params)
+    }
+
+    /// Check whether `ty` is one of the type parameters itself.
+    fn is_param(&self, ty: &Type) -> bool {
+        matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.get_ident().is_some_and(|ident| self.params.contains(ident)))
+    }
+}
+
+/// Build the field values of a simulated failure: `message` for the `member` field
+/// and `Default::default()` for every other one.
+///
+/// A message field whose type is a type parameter gets that type's own simulated
+/// failure instead. Fields typed by the type parameters add the bound they need to
+/// `bounds`; ones no bound can build are rejected.
+fn fields_init(
+    fields: &Fields,
+    message: &str,
+    member: Option<&syn::Member>,
+    generics: &GenericFields,
+    bounds: &mut Vec<syn::WherePredicate>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let krate = generics.krate;
+    let mut value_for = |field: &syn::Field, this: syn::Member| {
+        let ty = &field.ty;
+        if member == Some(&this) {
+            if generics.is_param(ty) {
+                bounds.push(syn::parse_quote! { #ty: #krate::FallibleError });
+                return Ok(quote! { <#ty as #krate::FallibleError>::simulated_failure() });
+            }
+            if generics.is_generic(ty) {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "FallibleError can't put the message in this generic field; \
+                     make it a `String` or a bare type parameter",
+                ));
+            }
+            return Ok(quote! { #message.to_string() });
+        }
+        if generics.is_generic(ty) {
+            if let Type::Reference(_) = ty {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "FallibleError can't build a default for a generic reference field",
+                ));
+            }
+            bounds.push(syn::parse_quote! { #ty: ::core::default::Default });
+        }
+        Ok(quote::quote_spanned! { ty.span()=> <#ty as ::core::default::Default>::default() })
+    };
+    Ok(match fields {
+        Fields::Unit => quote! {},
+        Fields::Named(fields) => {
+            let mut inits = Vec::with_capacity(fields.named.len());
+            for f in &fields.named {
+                let ident = f.ident.clone().expect("named field");
+                let value = value_for(f, syn::Member::Named(ident.clone()))?;
+                inits.push(quote! { #ident: #value });
+            }
+            quote! { { #(#inits),* } }
+        }
+        Fields::Unnamed(fields) => {
+            let mut values = Vec::with_capacity(fields.unnamed.len());
+            for (i, f) in fields.unnamed.iter().enumerate() {
+                values.push(value_for(f, syn::Member::Unnamed(i.into()))?);
+            }
+            quote! { ( #(#values),* ) }
+        }
+    })
+}
+
+/// Build the enum variant a derived `simulated_failure()` returns.
+///
+/// The message field (see `message_member`) gets the message; any other field
+/// falls back to `Default::default()`. Named variants without a message field
+/// can't be built, so they are rejected here instead of in the generated code.
+fn variant_constructor(
+    variant: &syn::Variant,
+    message: &str,
+    message_field: Option<&str>,
+    marked: bool,
+    generics: &GenericFields,
+    bounds: &mut Vec<syn::WherePredicate>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &variant.ident;
+    let member = message_member(&variant.fields, message_field);
+    if let Fields::Named(fields) = &variant.fields
+        && !fields.named.is_empty()
+        && member.is_none()
+    {
+        let chosen = if marked {
+            format!("the `#[fallible]` variant `{name}`")
+        } else {
+            format!("no variant is marked `#[fallible]`, so the first variant `{name}` is used, but it")
+        };
+        let field = message_field.unwrap_or("message");
+        return Err(syn::Error::new_spanned(
+            variant,
+            format!(
+                "FallibleError can't build a simulated failure: {chosen} has no `{field}` field; \
+                 mark a unit variant or one with a `{field}` field with `#[fallible]`"
+            ),
+        ));
+    }
+    let init = fields_init(&variant.fields, message, member.as_ref(), generics, bounds)?;
+    Ok(quote! { Self::#name #init })
+}
+
+/// Build the body of the `Display::fmt` generated by `#[fallible(derive_error)]`.
+///
+/// Prints the message field when there is one, otherwise the custom message of a
+/// struct or the name of the struct or variant.
+fn display_body(input: &DeriveInput, custom_message: Option<&str>, message_field: Option<&str>) -> proc_macro2::TokenStream {
+    match &input.data {
+        Data::Struct(data_struct) => match message_member(&data_struct.fields, message_field) {
+            Some(member) => quote! { ::core::fmt::Display::fmt(&self.#member, f) },
+            None => {
+                let text = custom_message.map_or_else(|| input.ident.to_string(), str::to_string);
+                quote! { f.write_str(#text) }
+            }
+        },
+        Data::Enum(data_enum) => {
+            let arms = data_enum.variants.iter().map(|v| {
+                let variant = &v.ident;
+                match (&v.fields, message_member(&v.fields, message_field)) {
+                    (Fields::Named(_), Some(member)) => quote! {
+                        Self::#variant { #member: message, .. } => ::core::fmt::Display::fmt(message, f),
+                    },
+                    (Fields::Unnamed(fields), Some(member)) => {
+                        let bindings = (0..fields.unnamed.len()).map(|i| {
+                            if member == syn::Member::Unnamed(i.into()) { quote! { message } } else { quote! { _ } }
+                        });
+                        quote! {
+                            Self::#variant(#(#bindings),*) => ::core::fmt::Display::fmt(message, f),
+                        }
+                    }
+                    _ => {
+                        let text = variant.to_string();
+                        quote! { Self::#variant { .. } => f.write_str(#text), }
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            let text = input.ident.to_string();
+            quote! { f.write_str(#text) }
+        }
+    }
+}
+
+/// Bounds for printing the generic message fields of a derived `Display`.
+fn display_bounds(input: &DeriveInput, message_field: Option<&str>, generics: &GenericFields) -> Vec<syn::WherePredicate> {
+    let fields: Vec<&Fields> = match &input.data {
+        Data::Struct(data_struct) => vec![&data_struct.fields],
+        Data::Enum(data_enum) => data_enum.variants.iter().map(|v| &v.fields).collect(),
+        Data::Union(_) => Vec::new(),
+    };
+    fields
+        .into_iter()
+        .filter_map(|fields| {
+            let member = message_member(fields, message_field)?;
+            let field = fields.iter().enumerate().find_map(|(i, f)| {
+                let this = match &f.ident {
+                    Some(ident) => syn::Member::Named(ident.clone()),
+                    None => syn::Member::Unnamed(i.into()),
+                };
+                (this == member).then_some(f)
+            })?;
+            let ty = &field.ty;
+            generics.is_generic(ty).then(|| syn::parse_quote! { #ty: ::core::fmt::Display })
+        })
+        .collect()
+}
+
+/// Copy `generics` with `bounds` added to its where-clause.
+fn with_bounds(generics: &syn::Generics, bounds: Vec<syn::WherePredicate>) -> syn::Generics {
+    let mut generics = generics.clone();
+    if !bounds.is_empty() {
+        generics.make_where_clause().predicates.extend(bounds);
+    }
+    generics
+}
+
+/// Parse a `"file:line"` or `"file:line:column"` location.
+fn parse_location(lit: &LitStr) -> syn::Result<(String, u32, u32)> {
+    let value = lit.value();
+    let mut parts = value.rsplitn(3, ':');
+    let numbers: Vec<_> = parts.by_ref().take(2).map(|part| part.parse::<u32>()).collect();
+    let parsed = match (numbers.as_slice(), parts.next()) {
+        ([Ok(column), Ok(line)], Some(file)) => Some((file.to_string(), *line, *column)),
+        ([Ok(line), Err(_)], None) => {
+            value.rsplit_once(':').map(|(file, _)| (file.to_string(), *line, 1))
+        }
+        _ => None,
+    };
+    match parsed {
+        Some((file, line, column)) if !file.is_empty() && line > 0 => Ok((file, line, column)),
+        _ => Err(syn::Error::new(
+            lit.span(),
+            "expected a location like \"src/lib.rs:42\" or \"src/lib.rs:42:5\"",
+        )),
+    }
+}
+
+/// Parse a `"N/D"` ratio with a non-zero denominator.
+fn parse_ratio(lit: &LitStr) -> syn::Result<(u64, u64)> {
+    let value = lit.value();
+    let parsed = value
+        .split_once('/')
+        .and_then(|(n, d)| Some((n.trim().parse().ok()?, d.trim().parse().ok()?)));
+    match parsed {
+        Some((n, d)) if d != 0 => Ok((n, d)),
+        _ => Err(syn::Error::new(
+            lit.span(),
+            "expected a ratio like \"1/3\" with a non-zero denominator",
+        )),
+    }
+}
+
+/// An attribute value given as a literal, checked when the macro expands, or as
+/// any other expression, such as a `const`, evaluated on each call.
+enum AttrValue<T> {
+    Lit(T),
+    Expr(syn::Expr),
+}
+
+impl<T> AttrValue<T> {
+    /// Parse an expression, converting it with `lit` when it's a literal.
+    ///
+    /// `lit` returns `None` for literals of the wrong kind, which are rejected
+    /// with `expected`.
+    fn parse(
+        input: syn::parse::ParseStream,
+        expected: &str,
+        lit: impl Fn(&syn::Lit) -> Option<syn::Result<T>>,
+    ) -> syn::Result<Self> {
+        let expr: syn::Expr = input.parse()?;
+        match &expr {
+            syn::Expr::Lit(expr_lit) => match lit(&expr_lit.lit) {
+                Some(value) => Ok(AttrValue::Lit(value?)),
+                None => Err(syn::Error::new_spanned(&expr, format!("expected {expected}, a const or an expression"))),
+            },
+            _ => Ok(AttrValue::Expr(expr)),
+        }
+    }
+}
+
 struct FallibleAttrs {
-    probability: Option<f64>,
-    trigger_every: Option<u64>,
-    enabled: Option<bool>,
+    probability: Option<AttrValue<f64>>,
+    ratio: Option<(u64, u64)>,
+    trigger_every: Option<AttrValue<u64>>,
+    offset: Option<u64>,
+    enabled: Option<AttrValue<bool>>,
+    off: bool,
+    severity: Option<Ident>,
+    when: Option<ExprClosure>,
+    krate: Option<Path>,
+    export_id: bool,
+    at_end: bool,
+    poison: bool,
+    default_error: bool,
+    by_args: bool,
+    break_with: Option<syn::Expr>,
+    feature: Option<LitStr>,
+    namespace: Option<LitStr>,
+    error: Option<Type>,
+    location: Option<(String, u32, u32)>,
 }
 
 impl Parse for FallibleAttrs {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut attrs = FallibleAttrs {
             probability: None,
+            ratio: None,
             trigger_every: None,
+            offset: None,
             enabled: None,
+            off: false,
+            severity: None,
+            when: None,
+            krate: None,
+            export_id: false,
+            at_end: false,
+            poison: false,
+            default_error: false,
+            by_args: false,
+            break_with: None,
+            feature: None,
+            namespace: None,
+            error: None,
+            location: None,
         };
 
         if input.is_empty() {
@@ -45,21 +610,126 @@ impl Parse for FallibleAttrs {
         }
 
         loop {
-            let key: Ident = input.parse()?;
-            input.parse::<Token![=]>()?;
+            let key = input.call(Ident::parse_any)?;
+            if key != "export_id" && key != "off" && key != "default_error" && key != "by_args" {
+                input.parse::<Token![=]>()?;
+            }
 
             match key.to_string().as_str() {
                 "probability" => {
-                    let lit: LitFloat = input.parse()?;
-                    attrs.probability = Some(lit.base10_parse()?);
+                    if attrs.ratio.is_some() {
+                        return Err(syn::Error::new(key.span(), "`probability` can't be combined with `ratio`"));
+                    }
+                    attrs.probability = Some(AttrValue::parse(input, "a float like `0.3`", |lit| match lit {
+                        syn::Lit::Float(lit) => Some(lit.base10_parse()),
+                        _ => None,
+                    })?);
+                }
+                "ratio" => {
+                    if attrs.probability.is_some() {
+                        return Err(syn::Error::new(key.span(), "`ratio` can't be combined with `probability`"));
+                    }
+                    let lit: LitStr = input.parse()?;
+                    attrs.ratio = Some(parse_ratio(&lit)?);
                 }
                 "trigger_every" => {
+                    attrs.trigger_every = Some(AttrValue::parse(input, "an integer", |lit| match lit {
+                        syn::Lit::Int(lit) => Some(lit.base10_parse()),
+                        _ => None,
+                    })?);
+                }
+                "offset" => {
                     let lit: LitInt = input.parse()?;
-                    attrs.trigger_every = Some(lit.base10_parse()?);
+                    let offset = lit.base10_parse()?;
+                    match &attrs.trigger_every {
+                        None => {
+                            return Err(syn::Error::new(lit.span(), "`offset` needs a `trigger_every` before it"));
+                        }
+                        Some(AttrValue::Lit(every)) if offset >= *every => {
+                            return Err(syn::Error::new(
+                                lit.span(),
+                                format!("`offset` must be less than `trigger_every` ({every})"),
+                            ));
+                        }
+                        Some(_) => {}
+                    }
+                    attrs.offset = Some(offset);
                 }
                 "enabled" => {
+                    attrs.enabled = Some(AttrValue::parse(input, "`true` or `false`", |lit| match lit {
+                        syn::Lit::Bool(lit) => Some(Ok(lit.value)),
+                        _ => None,
+                    })?);
+                }
+                "severity" => {
+                    let lit: LitStr = input.parse()?;
+                    let variant = match lit.value().as_str() {
+                        "low" => "Low",
+                        "medium" => "Medium",
+                        "high" => "High",
+                        "critical" => "Critical",
+                        _ => {
+                            return Err(syn::Error::new(
+                                lit.span(),
+                                "expected one of \"low\", \"medium\", \"high\" or \"critical\"",
+                            ));
+                        }
+                    };
+                    attrs.severity = Some(Ident::new(variant, lit.span()));
+                }
+                "when" => {
+                    attrs.when = Some(input.parse()?);
+                }
+                "crate" => {
+                    attrs.krate = Some(input.call(Path::parse_mod_style)?);
+                }
+                "export_id" => {
+                    attrs.export_id = true;
+                }
+                "off" => {
+                    attrs.off = true;
+                }
+                "feature" => {
+                    attrs.feature = Some(input.parse()?);
+                }
+                "namespace" => {
+                    attrs.namespace = Some(input.parse()?);
+                }
+                "error" => {
+                    attrs.error = Some(input.parse()?);
+                }
+                "break_with" => {
+                    attrs.break_with = Some(input.parse()?);
+                }
+                "location" => {
+                    let lit: LitStr = input.parse()?;
+                    attrs.location = Some(parse_location(&lit)?);
+                }
+                "poison" => {
                     let lit: LitBool = input.parse()?;
-                    attrs.enabled = Some(lit.value);
+                    if lit.value && attrs.default_error {
+                        return Err(syn::Error::new(lit.span(), "`poison` can't be combined with `default_error`"));
+                    }
+                    attrs.poison = lit.value;
+                }
+                "default_error" => {
+                    if attrs.poison {
+                        return Err(syn::Error::new(key.span(), "`default_error` can't be combined with `poison`"));
+                    }
+                    attrs.default_error = true;
+                }
+                "by_args" => {
+                    attrs.by_args = true;
+                }
+                "position" => {
+                    let lit: LitStr = input.parse()?;
+                    attrs.at_end = match lit.value().as_str() {
+                        "start" => false,
+                        "end" => true,
+                        _ => {
+                            return Err(syn::Error::new(lit.span(), "expected \"start\" or \"end\""));
+                        }
+                    };
                 }
                 _ => {
                     return Err(syn::Error::new(key.span(), "unknown attribute"));
@@ -83,13 +753,59 @@ impl Parse for FallibleAttrs {
 ///
 /// When failure injection is enabled via configuration, this function may return an error
 /// instead of executing normally. The function must return a `Result<T, E>` where `E`
-/// implements the `FallibleError` trait.
+/// implements the `FallibleError` trait, or a future resolving to one: `async fn`,
+/// `impl Future<Output = Result<T, E>>` or `Pin<Box<dyn Future<Output = Result<T, E>>>>`.
+/// `anyhow::Result<T>` and `eyre::Result<T>` are recognized by name; other
+/// single-argument `Result<T>` aliases need the `error` attribute. A function
+/// returning `ControlFlow<B, C>` breaks with `B::simulated_failure_for(fp)`, or with
+/// the `break_with` expression.
+///
+/// On an `unsafe fn` the check runs before any of the body, so an injected failure
+/// returns without having touched anything the body's safety contract covers.
+/// `position = "end"` is rejected there for the same reason.
 ///
 /// # Attributes
 ///
+/// `probability`, `ratio` and `trigger_every` replace the active config's probability and
+/// trigger interval for this function, unless the config was built with `override_inline(true)`.
+/// With no config installed nothing fails. `probability`, `trigger_every` and `enabled`
+/// accept consts and other expressions as well as literals; those are evaluated on each call.
+///
 /// - `probability = 0.0..1.0` - Set inline failure probability (0.0 to 1.0)
+/// - `ratio = "N/D"` - Set inline failure probability as an exact fraction, instead of `probability`
 /// - `trigger_every = N` - Fail every Nth call deterministically
-/// - `enabled = true/false` - Enable/disable this specific failure point
+/// - `offset = K` - With `trigger_every`, fail on calls K, K + N, K + 2N, ... instead of 0, N, 2N, ...;
+///   K must be less than N, or, when N is an expression, is taken modulo N
+/// - `enabled = true/false` - With `false` the point is off by default and only fails once a
+///   config enables it by name with `enable_named()` or `enable_point()`
+/// - `off` - Compile the check out entirely, leaving the function as written
+/// - `severity = "low" | "medium" | "high" | "critical"` - Blast radius of this point (default `"medium"`)
+/// - `when = |arg, ..| bool` - Only consider failing when the closure returns true; parameters
+///   are matched to the function's arguments by name and receive references to them
+/// - `crate = path` - Path to `fallibles-core` in generated code (default `::fallibles::fallibles_core`),
+///   for crates that depend on `fallibles-core` and `fallibles-macro` without the facade
+/// - `position = "start" | "end"` - Where the check runs (default `"start"`); `"end"` runs it
+///   just before the final expression, after the body's side effects. Early returns skip it,
+///   and `when` can only name arguments that are still alive at that point
+/// - `poison = true` - Instead of an error, return `Ok(T::poisoned())` (see `PoisonValue`) to test
+///   handling of degenerate but valid results
+/// - `break_with = expr` - For functions returning `ControlFlow<B, C>`, break with `expr`
+///   instead of `B::simulated_failure_for(fp)`; `B` then needs no `FallibleError` impl
+/// - `by_args` - Decide probability checks from a hash of the arguments (other than
+///   `self`) instead of the call counter, so the same arguments always pass or always
+///   fail for a given seed; the arguments must implement `Hash`. Uses the config's
+///   probability unless `probability` or `ratio` is also given
+/// - `default_error` - Build the injected error with `Default::default()`, for error types
+///   that implement `Default` but not `FallibleError`
+/// - `feature = "name"` - Only inject when the calling crate also enables this cargo feature,
+///   on top of `fallibles-sim`
+/// - `export_id` - Also emit `const <NAME>_FAILURE_ID: FailurePointId` next to the function,
+///   with the function's visibility
+/// - `namespace = "name"` - Derive the point's id from `"name::function"` instead of the bare
+///   function name, so same-named functions in different crates or modules don't share an id
+/// - `error = Type` - Error type of a single-argument `Result<T>` alias such as `io::Result<T>`
+/// - `location = "file:line"` or `"file:line:column"` - Report this source location in the
+///   `FailurePoint` instead of the function's own, for functions generated by other macros
 ///
 /// # Examples
 ///
@@ -105,163 +821,558 @@ impl Parse for FallibleAttrs {
 ///
 /// With inline probability:
 /// ```rust
+/// # use fallibles::fallible;
 /// #[fallible(probability = 0.2)]  // 20% failure rate
 /// fn unstable_api() -> Result<i32, &'static str> {
 ///     Ok(42)
 /// }
 /// ```
 ///
+/// With an exact ratio:
+/// ```rust
+/// # use fallibles::fallible;
+/// #[fallible(ratio = "1/3")]  // one in three calls fails
+/// fn flaky_lookup() -> Result<i32, &'static str> {
+///     Ok(7)
+/// }
+/// ```
+///
+/// A ratio and a probability can't both be given:
+/// ```compile_fail
+/// # use fallibles::fallible;
+/// #[fallible(ratio = "1/1000", probability = 1.0)]  // error: can't be combined
+/// fn flaky_lookup() -> Result<i32, &'static str> {
+///     Ok(7)
+/// }
+/// ```
+///
 /// Deterministic failures:
 /// ```rust
+/// # use fallibles::fallible;
 /// #[fallible(trigger_every = 5)]  // Fail every 5th call
 /// fn periodic_task() -> Result<(), String> {
 ///     Ok(())
 /// }
 /// ```
 ///
-/// Works with async functions:
+/// An `offset` past the interval is a compile error:
+/// ```compile_fail
+/// # use fallibles::fallible;
+/// #[fallible(trigger_every = 2, offset = 5)]  // error: must be less than `trigger_every`
+/// fn periodic_task() -> Result<(), String> {
+///     Ok(())
+/// }
+/// ```
+///
+/// With a severity level:
+/// ```rust
+/// # use fallibles::fallible;
+/// #[fallible(severity = "critical")]  // Only fails when the config allows critical points
+/// fn drop_table() -> Result<(), String> {
+///     Ok(())
+/// }
+/// ```
+///
+/// Conditional on arguments:
+/// ```rust
+/// # use fallibles::fallible;
+/// #[fallible(when = |amount| *amount > 1000)]  // Small payments never fail
+/// fn charge(account: u32, amount: u64) -> Result<(), String> {
+///     Ok(())
+/// }
+/// ```
+///
+/// Works with generic functions; an error type built from type parameters gets a
+/// `FallibleError` bound added for you:
+/// ```rust
+/// # use fallibles::fallible;
+/// use std::str::FromStr;
+///
+/// #[fallible]
+/// fn parse<T: FromStr>(input: &str) -> Result<T, T::Err> {
+///     input.parse()
+/// }
+/// ```
+///
+/// Checking after the body's side effects:
+/// ```rust
+/// # use fallibles::fallible;
+/// #[fallible(position = "end")]  // The file is written even when the call fails
+/// fn save(path: &str) -> Result<(), String> {
+///     std::fs::write(path, b"data").map_err(|e| e.to_string())?;
+///     Ok(())
+/// }
+/// ```
+///
+/// Returning a poisoned success value instead of an error:
+/// ```rust
+/// # use fallibles::fallible;
+/// #[fallible(poison = true)]  // Returns `Ok(0)` when triggered
+/// fn account_balance() -> Result<u64, String> {
+///     Ok(100)
+/// }
+/// ```
+///
+/// Only injecting in one subsystem, behind the calling crate's `chaos_db` feature:
+/// ```rust
+/// # use fallibles::fallible;
+/// #[fallible(feature = "chaos_db")]
+/// fn run_query() -> Result<(), String> {
+///     Ok(())
+/// }
+/// ```
+///
+/// Exporting the failure point id:
+/// ```rust
+/// # use fallibles::fallible;
+/// use fallibles::fallibles_core::FailureConfig;
+///
+/// #[fallible(export_id)]  // Emits `READ_MANIFEST_FAILURE_ID`
+/// fn read_manifest() -> Result<(), String> {
+///     Ok(())
+/// }
+///
+/// let config = FailureConfig::enable_all().enable_point(READ_MANIFEST_FAILURE_ID);
+/// ```
+///
+/// Works with functions returning futures without `async fn`:
+/// ```rust
+/// # use fallibles::fallible;
+/// use std::future::Future;
+///
+/// #[fallible]
+/// fn fetch_legacy() -> impl Future<Output = Result<u32, String>> {
+///     async { Ok(7) }
+/// }
+/// ```
+///
+/// Works on trait methods with a default body:
 /// ```rust
+/// # use fallibles::fallible;
+/// trait Store {
+///     #[fallible]
+///     fn load(&self) -> Result<u32, String> {
+///         Ok(1)
+///     }
+/// }
+/// ```
+///
+/// Required trait methods have no body to inject into and are rejected:
+/// ```compile_fail
+/// # use fallibles::fallible;
+/// trait Store {
+///     #[fallible]
+///     fn save(&self) -> Result<(), String>;
+/// }
+/// ```
+///
+/// Other attributes and doc comments on the function are kept on both the injected and
+/// the plain version, including those written below `#[fallible]`.
+///
+/// Works with async functions. The check, including the call count behind inline
+/// `trigger_every` and `probability`, runs when the future is first polled; for
+/// functions returning `impl Future` or a boxed future it runs when they are called:
+/// ```rust
+/// # use fallibles::fallible;
 /// #[fallible]
 /// async fn fetch_data() -> Result<Vec<u8>, std::io::Error> {
 ///     Ok(vec![1, 2, 3])
 /// }
 /// ```
+///
+/// Without the `fallibles-sim` feature the function is emitted exactly as written, so an
+/// annotated `async fn` produces the same future as an unannotated one:
+/// ```rust
+/// # use fallibles::fallible;
+/// #[fallible]
+/// async fn annotated(ids: &[u32]) -> Result<Vec<u32>, String> {
+///     let first: u32 = "1".parse().map_err(|_| "bad id")?;
+///     Ok(ids.iter().map(|id| id + first).collect())
+/// }
+///
+/// async fn plain(ids: &[u32]) -> Result<Vec<u32>, String> {
+///     let first: u32 = "1".parse().map_err(|_| "bad id")?;
+///     Ok(ids.iter().map(|id| id + first).collect())
+/// }
+///
+/// # #[cfg(not(feature = "fallibles-sim"))]
+/// assert_eq!(size_of_val(&annotated(&[1])), size_of_val(&plain(&[1])));
+/// ```
+///
+/// A `const fn` can't run the check, so it is rejected with the feature enabled and
+/// left as written, still usable in const contexts, without it:
+/// ```rust
+/// # use fallibles::fallible;
+/// #[fallible]
+/// const fn batch_size() -> Result<usize, &'static str> {
+///     Ok(64)
+/// }
+///
+/// # #[cfg(not(feature = "fallibles-sim"))]
+/// const BATCH: Result<usize, &'static str> = batch_size();
+/// ```
 #[proc_macro_attribute]
 pub fn fallible(attr: TokenStream, item: TokenStream) -> TokenStream {
     let attrs = parse_macro_input!(attr as FallibleAttrs);
-    let input = parse_macro_input!(item as ItemFn);
 
+    // Trait methods with a default body parse as an `ItemFn` without visibility.
+    // Required methods have nothing to inject into.
+    let input = match syn::parse::<ItemFn>(item.clone()) {
+        Ok(input) => input,
+        Err(err) => {
+            if let Ok(method) = syn::parse::<TraitItemFn>(item)
+                && method.default.is_none()
+            {
+                return syn::Error::new_spanned(
+                    &method.sig,
+                    "`#[fallible]` needs a function body; put it on a default body or on the implementations",
+                )
+                .to_compile_error()
+                .into();
+            }
+            return err.to_compile_error().into();
+        }
+    };
+
+    let krate = crate_path(attrs.krate.as_ref());
+    let fn_attrs = &input.attrs;
     let sig = &input.sig;
     let block = &input.block;
     let vis = &input.vis;
     let is_async = sig.asyncness.is_some();
 
     let fn_name = sig.ident.to_string();
-    let id_hash = fxhash::hash32(fn_name.as_bytes());
+    let id_name = match &attrs.namespace {
+        Some(namespace) => format!("{}::{}", namespace.value(), fn_name),
+        None => fn_name.clone(),
+    };
+    let id_hash = fallibles_core::FailurePointId::from_name(&id_name).0;
 
-    let error_type = extract_result_error_type(&sig.output);
+    let export_id = attrs.export_id.then(|| {
+        let const_name = Ident::new(
+            &format!("{}_FAILURE_ID", sig.ident.unraw().to_string().to_uppercase()),
+            sig.ident.span(),
+        );
+        let doc = format!("Failure point id of `{}`.", sig.ident.unraw());
+        quote! {
+            #[doc = #doc]
+            #vis const #const_name: #krate::FailurePointId = #krate::FailurePointId(#id_hash);
+        }
+    });
+
+    let shape = return_shape(&sig.output, attrs.error.as_ref());
+    let fail = |result: proc_macro2::TokenStream| match &shape {
+        Some(ReturnShape::ImplFuture(_)) => quote! {
+            return #krate::InjectedFuture::Failed(Some(#result));
+        },
+        Some(ReturnShape::BoxedFuture(_)) => quote! {
+            return #krate::__private::Box::pin(::core::future::ready(#result));
+        },
+        _ => quote! { return #result; },
+    };
 
-    let check_logic = if let Some(enabled) = attrs.enabled {
-        if !enabled {
-            return quote! { #vis #sig #block }.into();
+    let severity = attrs
+        .severity
+        .unwrap_or_else(|| Ident::new("Medium", proc_macro2::Span::call_site()));
+    let (file, line, column) = match &attrs.location {
+        Some((file, line, column)) => (quote! { #file }, quote! { #line }, quote! { #column }),
+        None => (quote! { file!() }, quote! { line!() }, quote! { column!() }),
+    };
+    let failure_point = quote! {
+        #krate::FailurePoint {
+            id: #krate::FailurePointId(#id_hash),
+            function: #fn_name,
+            file: #file,
+            line: #line,
+            column: #column,
+            severity: #krate::Severity::#severity,
         }
+    };
+
+    // A poisoned point succeeds with a degenerate value instead of failing.
+    let control_flow = shape.as_ref().is_some_and(|shape| shape.types().control_flow);
+    if let Some(expr) = &attrs.break_with
+        && !control_flow
+    {
+        return syn::Error::new_spanned(expr, "`break_with` needs a function returning `ControlFlow`")
+            .to_compile_error()
+            .into();
+    }
+    let (ok_variant, err_variant) = if control_flow {
+        (quote! { ::core::ops::ControlFlow::Continue }, quote! { ::core::ops::ControlFlow::Break })
+    } else {
+        (quote! { Ok }, quote! { Err })
+    };
+    let (injected_type, injected_bound) = match shape.as_ref().map(ReturnShape::types) {
+        Some(types) if attrs.poison => (Some(types.ok), quote! { #krate::PoisonValue }),
+        Some(_) if attrs.break_with.is_some() => (None, quote! {}),
+        types if attrs.default_error => (types.map(|types| types.err), quote! { ::core::default::Default }),
+        types => (types.map(|types| types.err), quote! { #krate::FallibleError }),
+    };
+    let build_err = if let Some(expr) = &attrs.break_with {
+        quote! { #expr }
+    } else if attrs.default_error {
+        quote! { <#injected_type as ::core::default::Default>::default() }
+    } else {
+        quote! { <#injected_type as #krate::FallibleError>::simulated_failure_for(fp) }
+    };
+    let config_fail = fail(if attrs.poison {
+        quote! { #ok_variant(<#injected_type as #krate::PoisonValue>::poisoned()) }
+    } else {
         quote! {
-            if ::fallibles::fallibles_core::should_simulate_failure(
-                ::fallibles::fallibles_core::FailurePoint {
-                    id: ::fallibles::fallibles_core::FailurePointId(#id_hash),
-                    function: #fn_name,
-                    file: file!(),
-                    line: line!(),
-                    column: column!(),
-                }
-            ) {
-                return Err(<#error_type as ::fallibles::fallibles_core::FallibleError>::simulated_failure());
+            {
+                use #krate::__private::{ReportDebug as _, ReportOpaque as _};
+                let err = {
+                    let _scope = #krate::__private::PointScope::build(fp);
+                    #build_err
+                };
+                (&#krate::__private::ErrorValue(&err)).report(fp);
+                #err_variant(err)
             }
         }
-    } else if let Some(prob) = attrs.probability {
-        let prob_u32 = (prob * u32::MAX as f64) as u32;
-        let id_bytes = id_hash.to_le_bytes();
-        quote! {
-            {
-                let mut bytes = [0u8; 12];
-                bytes[0..4].copy_from_slice(&[#(#id_bytes),*]);
-                static COUNTER: ::core::sync::atomic::AtomicU64 = ::core::sync::atomic::AtomicU64::new(0);
-                let counter = COUNTER.fetch_add(1, ::core::sync::atomic::Ordering::Relaxed);
-                bytes[4..12].copy_from_slice(&counter.to_le_bytes());
-
-                let hash1 = ::fallibles::fxhash::hash32(&bytes);
-                let hash2 = ::fallibles::fxhash::hash64(&bytes);
-
-                let mut combined = (hash1 as u64) ^ hash2;
+    });
 
-                #[cfg(feature = "std")]
+    // Inline attributes still go through the active config, replacing only its
+    // probability and trigger interval for this point.
+    // `enabled = false` points only fail once a config enables them by name.
+    // Literal rates are converted here; other expressions on each call.
+    let inline_probability = match (attrs.ratio, &attrs.probability) {
+        (Some((n, d)), _) => {
+            let threshold = fallibles_core::ratio_threshold(n, d);
+            Some(quote! { #threshold })
+        }
+        (None, Some(AttrValue::Lit(probability))) => {
+            let threshold = fallibles_core::probability_threshold(*probability);
+            Some(quote! { #threshold })
+        }
+        (None, Some(AttrValue::Expr(expr))) => Some(quote! { #krate::probability_threshold(#expr) }),
+        (None, None) => None,
+    };
+    let trigger_every = attrs.trigger_every.as_ref().map(|every| match every {
+        AttrValue::Lit(every) => quote! { #every },
+        AttrValue::Expr(expr) => quote! { #expr },
+    });
+    let opt_in = match &attrs.enabled {
+        Some(AttrValue::Lit(enabled)) => {
+            let opt_in = !enabled;
+            quote! { #opt_in }
+        }
+        Some(AttrValue::Expr(_)) => quote! { !enabled },
+        None => quote! { false },
+    };
+    let own_rate = inline_probability.is_some() || trigger_every.is_some() || attrs.by_args;
+    let args_hash = if attrs.by_args {
+        if attrs.trigger_every.is_some() && inline_probability.is_none() {
+            return syn::Error::new_spanned(&sig.ident, "`by_args` decides probabilities and can't be used with `trigger_every`")
+                .to_compile_error()
+                .into();
+        }
+        match args_hash(sig, &krate) {
+            Ok(hash) => quote! { Some(#hash) },
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote! { None }
+    };
+    let inline_policy = (own_rate || !matches!(attrs.enabled, None | Some(AttrValue::Lit(true)))).then(|| {
+        let (probability, trigger_every, trigger_offset) = match (inline_probability, trigger_every) {
+            (Some(probability), _) => (probability, quote! { 0 }, 0),
+            (None, every) => (quote! { 0 }, every.unwrap_or_else(|| quote! { 0 }), attrs.offset.unwrap_or(0)),
+        };
+        quote! {
+            #krate::InlinePolicy {
+                probability: #probability,
+                trigger_every: #trigger_every,
+                trigger_offset: #trigger_offset,
+                opt_in: #opt_in,
+                args_hash: #args_hash,
+            }
+        }
+    });
+    let simulate = |policy: Option<&proc_macro2::TokenStream>| match (policy, is_async) {
+        (Some(policy), true) => quote! { #krate::should_simulate_failure_inline_async(fp, #policy).await },
+        (Some(policy), false) => quote! { #krate::should_simulate_failure_inline(fp, #policy) },
+        (None, true) => quote! { #krate::should_simulate_failure_async(fp).await },
+        (None, false) => quote! { #krate::should_simulate_failure(fp) },
+    };
+    let should_simulate = match &attrs.enabled {
+        // A runtime `enabled` is evaluated once; while it's true a point without a
+        // rate of its own checks like an unannotated one.
+        Some(AttrValue::Expr(expr)) => {
+            let check = if own_rate {
+                simulate(inline_policy.as_ref())
+            } else {
+                let plain = simulate(None);
+                let opt_in = simulate(inline_policy.as_ref());
+                quote! { if enabled { #plain } else { #opt_in } }
+            };
+            quote! {
                 {
-                    let nanos = ::std::time::SystemTime::now()
-                        .duration_since(::std::time::UNIX_EPOCH)
-                        .map(|d| d.as_nanos() as u64)
-                        .unwrap_or(0);
-                    let thread_id = ::std::thread::current().id();
-                    let thread_hash = ::fallibles::fxhash::hash64(&::std::format!("{:?}", thread_id).as_bytes());
-                    let stack_addr = &nanos as *const _ as usize as u64;
-                    combined ^= nanos.wrapping_add(stack_addr).wrapping_mul(thread_hash);
+                    let enabled: bool = #expr;
+                    #check
                 }
+            }
+        }
+        _ => simulate(inline_policy.as_ref()),
+    };
 
-                combined ^= combined >> 33;
-                combined = combined.wrapping_mul(0xff51afd7ed558ccd);
-                combined ^= combined >> 33;
-                combined = combined.wrapping_mul(0xc4ceb9fe1a85ec53);
-                combined ^= combined >> 33;
+    if attrs.off {
+        return quote! { #export_id #(#fn_attrs)* #vis #sig #block }.into();
+    }
 
-                let threshold = ((#prob_u32 as u64) << 32) | #prob_u32 as u64;
-                if combined < threshold {
-                    return Err(<#error_type as ::fallibles::fallibles_core::FallibleError>::simulated_failure());
-                }
-            }
+    // A `const fn` can't call into the runtime, but without the feature(s) the
+    // untouched function is fine.
+    let sim_cfg = match &attrs.feature {
+        Some(feature) => quote! { all(feature = "fallibles-sim", feature = #feature) },
+        None => quote! { feature = "fallibles-sim" },
+    };
+    if let Some(constness) = &sig.constness {
+        let error = quote::quote_spanned! { constness.span=>
+            ::core::compile_error!(
+                "`#[fallible]` cannot be used on `const fn`; remove `const` or the attribute"
+            );
+        };
+        return quote! {
+            #export_id
+
+            #[cfg(#sim_cfg)]
+            #error
+
+            #[cfg(not(#sim_cfg))]
+            #(#fn_attrs)*
+            #vis #sig #block
         }
-    } else if let Some(every) = attrs.trigger_every {
-        quote! {
-            {
-                static COUNTER: ::core::sync::atomic::AtomicU64 = ::core::sync::atomic::AtomicU64::new(0);
-                let count = COUNTER.fetch_add(1, ::core::sync::atomic::Ordering::Relaxed);
-                if count % #every == 0 {
-                    return Err(<#error_type as ::fallibles::fallibles_core::FallibleError>::simulated_failure());
-                }
+        .into();
+    }
+    let check_logic = quote! {
+        {
+            let fp = #failure_point;
+            if #should_simulate {
+                #config_fail
             }
         }
-    } else {
-        quote! {
-            if ::fallibles::fallibles_core::should_simulate_failure(
-                ::fallibles::fallibles_core::FailurePoint {
-                    id: ::fallibles::fallibles_core::FailurePointId(#id_hash),
-                    function: #fn_name,
-                    file: file!(),
-                    line: line!(),
-                    column: column!(),
+    };
+
+    let check_logic = match &attrs.when {
+        Some(closure) => {
+            let call = match when_call(closure, sig) {
+                Ok(call) => call,
+                Err(err) => return err.to_compile_error().into(),
+            };
+            quote! {
+                if #call {
+                    #check_logic
                 }
-            ) {
-                return Err(<#error_type as ::fallibles::fallibles_core::FallibleError>::simulated_failure());
             }
         }
+        None => check_logic,
+    };
+
+    let Some(shape) = &shape else {
+        return quote! { #export_id #(#fn_attrs)* #vis #sig #block }.into();
     };
 
-    let expanded = if let Some(_err_ty) = error_type {
+    let injected = if attrs.at_end {
+        // Failing at the end would return without the final expression, which an
+        // `unsafe fn` may need to restore the invariants its callers rely on.
+        if let Some(unsafety) = &sig.unsafety {
+            return syn::Error::new_spanned(
+                unsafety,
+                "`position = \"end\"` is not supported on `unsafe fn`; the injected return would skip \
+                 the end of the body",
+            )
+            .to_compile_error()
+            .into();
+        }
+        if let ReturnShape::ImplFuture(_) = shape {
+            return syn::Error::new_spanned(
+                &sig.output,
+                "`position = \"end\"` is not supported for functions returning `impl Future`",
+            )
+            .to_compile_error()
+            .into();
+        }
+
+        let block = check_at_end(block, &check_logic);
         if is_async {
             quote! {
-                #vis #sig {
-                    #[cfg(feature = "fallibles-sim")]
-                    #check_logic
-
-                    let result = async #block;
-                    result.await
-                }
+                let result = async #block;
+                result.await
             }
         } else {
-            quote! {
-                #vis #sig {
-                    #[cfg(feature = "fallibles-sim")]
-                    #check_logic
+            block
+        }
+    } else if let ReturnShape::ImplFuture(_) = shape {
+        quote! {
+            #check_logic
 
-                    #block
-                }
-            }
+            #krate::InjectedFuture::Inner((move || #block)())
+        }
+    } else if is_async {
+        quote! {
+            #check_logic
+
+            let result = async #block;
+            result.await
         }
     } else {
         quote! {
-            #vis #sig #block
+            #check_logic
+
+            #block
         }
     };
 
+    // Without the feature(s) the original function is emitted untouched.
+    let injected_sig = match injected_type {
+        Some(ty) => bound_generic_type(sig, &ty, injected_bound),
+        None => sig.clone(),
+    };
+    let expanded = quote! {
+        #export_id
+
+        #[cfg(#sim_cfg)]
+        #(#fn_attrs)*
+        #vis #injected_sig {
+            #krate::__register_point!(#failure_point);
+
+            #injected
+        }
+
+        #[cfg(not(#sim_cfg))]
+        #(#fn_attrs)*
+        #vis #sig #block
+    };
+
     expanded.into()
 }
 
 /// Derive the `FallibleError` trait for custom error types.
 ///
 /// Implements `FallibleError::simulated_failure()` for your error type.
+/// `simulated_failure_for()` keeps its default and returns the same value.
 ///
 /// # Attributes
 ///
 /// - `#[fallible(message = "...")]` - Custom error message (struct/enum level)
-/// - `#[fallible]` - Mark a specific enum variant to use for failures
+/// - `#[fallible(message_field = "name")]` - Field that receives the message instead of
+///   `message` or the only tuple field; a number picks a tuple field. Other fields
+///   get `Default::default()`
+/// - `#[fallible]` - Mark a specific enum variant to use for failures; when several
+///   variants are marked, successive failures cycle through them in declaration order
+/// - `#[fallible(crate = path)]` - Path to `fallibles-core` in the generated impl
+/// - `#[fallible(derive_error)]` - Also implement `Display`, printing the `message`
+///   field (or only tuple field) and otherwise the struct's message or the variant
+///   name, and `core::error::Error`, which needs a `Debug` impl
+///
+/// Enums also return the variant named by `fallibles_core::requested_variant()`,
+/// set by an `Outcome::ErrVariant` in `FailureConfig::sequence()`, when it can be
+/// built.
+///
+/// In generic types, a message field typed by a bare type parameter `E` gets
+/// `E::simulated_failure()` and adds `E: FallibleError` to the impl; other fields
+/// typed by the parameters add a `Default` bound. Only the struct or the variants
+/// used for simulated failures contribute bounds.
 ///
 /// # Examples
 ///
@@ -278,6 +1389,7 @@ pub fn fallible(attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// Enum with marked variant:
 /// ```rust
+/// # use fallibles::FallibleError;
 /// #[derive(Debug, FallibleError)]
 /// enum NetworkError {
 ///     #[fallible]  // This variant will be used for simulated failures
@@ -286,90 +1398,326 @@ pub fn fallible(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// }
 /// ```
 ///
+/// Enum with several marked variants, used in turn:
+/// ```rust
+/// # use fallibles::FallibleError;
+/// #[derive(Debug, PartialEq, FallibleError)]
+/// enum DiskError {
+///     #[fallible]
+///     Full,
+///     #[fallible]
+///     ReadOnly,
+///     NotFound,
+///     #[fallible]
+///     Io { message: String },
+/// }
+///
+/// assert_eq!(DiskError::simulated_failure(), DiskError::Full);
+/// assert_eq!(DiskError::simulated_failure(), DiskError::ReadOnly);
+/// assert_eq!(
+///     DiskError::simulated_failure(),
+///     DiskError::Io { message: "simulated failure".to_string() }
+/// );
+/// assert_eq!(DiskError::simulated_failure(), DiskError::Full);
+/// ```
+///
+/// Message in a field of your choice:
+/// ```rust
+/// # use fallibles::FallibleError;
+/// #[derive(Debug, PartialEq, FallibleError)]
+/// #[fallible(message = "quota exceeded", message_field = "detail")]
+/// struct QuotaError {
+///     code: u16,
+///     tenant: String,
+///     detail: String,
+/// }
+///
+/// assert_eq!(
+///     QuotaError::simulated_failure(),
+///     QuotaError { code: 0, tenant: String::new(), detail: "quota exceeded".to_string() }
+/// );
+///
+/// #[derive(Debug, PartialEq, FallibleError)]
+/// #[fallible(message_field = "1")]
+/// struct HttpError(u16, String);
+///
+/// assert_eq!(HttpError::simulated_failure(), HttpError(0, "simulated failure".to_string()));
+/// ```
+///
+/// A misspelled attribute is a compile error rather than silently ignored:
+/// ```compile_fail
+/// # use fallibles::FallibleError;
+/// #[derive(Debug, FallibleError)]
+/// #[fallible(mesage = "quota exceeded")]  // error: unknown attribute
+/// struct QuotaError {
+///     message: String,
+/// }
+/// ```
+///
+/// Naming a field that doesn't exist is a compile error:
+/// ```compile_fail
+/// # use fallibles::FallibleError;
+/// #[derive(Debug, FallibleError)]
+/// #[fallible(message_field = "details")]  // error: `QuotaError` has no field `details`
+/// struct QuotaError {
+///     detail: String,
+/// }
+/// ```
+///
+/// Generic types:
+/// ```rust
+/// # use fallibles::FallibleError;
+/// #[derive(Debug, PartialEq, FallibleError)]
+/// enum DiskError {
+///     #[fallible]
+///     Full,
+/// }
+///
+/// #[derive(Debug, PartialEq, FallibleError)]
+/// enum LayerError<E, C> {
+///     #[fallible]
+///     Inner(E),
+///     WithContext(C),
+/// }
+///
+/// #[derive(Debug, PartialEq, FallibleError)]
+/// struct Tagged<T> {
+///     tag: T,
+///     message: String,
+/// }
+///
+/// // `C` isn't used for simulated failures, so it needs no bound
+/// #[derive(Debug, PartialEq)]
+/// struct NotDefault;
+/// assert_eq!(
+///     LayerError::<DiskError, NotDefault>::simulated_failure(),
+///     LayerError::Inner(DiskError::Full)
+/// );
+/// assert_eq!(
+///     Tagged::<u8>::simulated_failure(),
+///     Tagged { tag: 0, message: "simulated failure".to_string() }
+/// );
+/// ```
+///
+/// A generic field no bound can build is a compile error:
+/// ```compile_fail
+/// # use fallibles::FallibleError;
+/// #[derive(Debug, FallibleError)]
+/// struct Borrowed<'a, T> {
+///     value: &'a T,  // error: can't build a default for a generic reference field
+///     message: String,
+/// }
+/// ```
+///
 /// Unit struct:
 /// ```rust
+/// # use fallibles::FallibleError;
 /// #[derive(Debug, FallibleError)]
 /// struct SimpleError;
 /// ```
+///
+/// With `Display` and `Error` impls, so the type works with `?` into a
+/// `Box<dyn Error>`:
+/// ```rust
+/// use fallibles::FallibleError;
+/// use std::error::Error;
+///
+/// #[derive(Debug, FallibleError)]
+/// #[fallible(derive_error)]
+/// enum UploadError {
+///     TooLarge { message: String },
+///     Rejected,
+/// }
+///
+/// fn upload(size: usize) -> Result<(), UploadError> {
+///     if size > 10 {
+///         return Err(UploadError::TooLarge { message: format!("{size} bytes is too large") });
+///     }
+///     Err(UploadError::Rejected)
+/// }
+///
+/// fn run(size: usize) -> Result<(), Box<dyn Error>> {
+///     upload(size)?;
+///     Ok(())
+/// }
+///
+/// assert_eq!(run(20).unwrap_err().to_string(), "20 bytes is too large");
+/// assert_eq!(run(5).unwrap_err().to_string(), "Rejected");
+/// assert_eq!(UploadError::simulated_failure().to_string(), "simulated failure");
+/// ```
+///
+/// # Choosing an Enum Variant
+///
+/// Without a `#[fallible]` marker the first variant is used. The chosen variant
+/// must be a unit variant, a single-field tuple variant, or have a `message`
+/// field; any other fields are filled with `Default::default()`. A variant that
+/// can't be built this way is rejected at compile time:
+///
+/// ```compile_fail
+/// # use fallibles::FallibleError;
+/// #[derive(Debug, FallibleError)]
+/// enum StorageError {
+///     // error: no variant is marked `#[fallible]`, so the first variant
+///     // `Corrupted` is used, but it has no `message` field
+///     Corrupted { block: u64, checksum: u32 },
+///     Unavailable,
+/// }
+/// ```
+///
+/// Marking a suitable variant fixes it:
+///
+/// ```rust
+/// # use fallibles::FallibleError;
+/// #[derive(Debug, FallibleError)]
+/// enum StorageError {
+///     Corrupted { block: u64, checksum: u32 },
+///     #[fallible]
+///     Unavailable,
+/// }
+/// ```
+///
+/// So does giving the variant a `message` field:
+///
+/// ```rust
+/// # use fallibles::FallibleError;
+/// #[derive(Debug, FallibleError)]
+/// enum StorageError {
+///     Corrupted { block: u64, message: String },
+///     Unavailable,
+/// }
+/// ```
 #[proc_macro_derive(FallibleError, attributes(fallible))]
 pub fn derive_fallible_error(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let name = &input.ident;
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
-    let custom_message = input.attrs.iter().find_map(|attr| {
+    let mut krate = None;
+    let mut derive_error = false;
+    let mut custom_message = None;
+    let mut message_field: Option<LitStr> = None;
+    for attr in &input.attrs {
         if attr.path().is_ident("fallible")
-            && let Meta::NameValue(nv) = &attr.meta
-            && nv.path.is_ident("message")
-            && let syn::Expr::Lit(expr_lit) = &nv.value
-            && let Lit::Str(lit_str) = &expr_lit.lit
+            && let Meta::List(_) = &attr.meta
         {
-            return Some(lit_str.value());
-        }
-        None
-    });
-
-    let error_expr = match &input.data {
-        Data::Struct(data_struct) => match &data_struct.fields {
-            Fields::Named(_) => {
-                if let Some(msg) = custom_message {
-                    quote! { Self { message: #msg.to_string() } }
+            let parsed = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("crate") {
+                    krate = Some(meta.value()?.call(Path::parse_mod_style)?);
+                } else if meta.path.is_ident("derive_error") {
+                    derive_error = true;
+                } else if meta.path.is_ident("message") {
+                    custom_message = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("message_field") {
+                    message_field = Some(meta.value()?.parse()?);
                 } else {
-                    quote! { Self { message: "simulated failure".to_string() } }
+                    return Err(meta.error("unknown attribute"));
                 }
+                Ok(())
+            });
+            if let Err(err) = parsed {
+                return err.to_compile_error().into();
             }
-            Fields::Unnamed(fields) => {
-                if fields.unnamed.len() == 1 {
-                    if let Some(msg) = custom_message {
-                        quote! { Self(#msg.to_string()) }
-                    } else {
-                        quote! { Self("simulated failure".to_string()) }
-                    }
-                } else {
-                    quote! { Self(Default::default()) }
-                }
+        }
+    }
+    let krate = crate_path(krate.as_ref());
+    let message = custom_message.as_deref().unwrap_or("simulated failure");
+    let field_name = message_field.as_ref().map(LitStr::value);
+    let field_name = field_name.as_deref();
+    let generics = GenericFields {
+        krate: &krate,
+        params: input.generics.type_params().map(|param| param.ident.clone()).collect(),
+    };
+    let mut bounds = Vec::new();
+
+    let error_expr = match &input.data {
+        Data::Struct(data_struct) => {
+            let fields = &data_struct.fields;
+            let member = message_member(fields, field_name);
+            let missing = match fields {
+                Fields::Named(named) => !named.named.is_empty() && member.is_none(),
+                _ => field_name.is_some() && member.is_none(),
+            };
+            if missing {
+                let err = match &message_field {
+                    Some(lit) => syn::Error::new(lit.span(), format!("`{name}` has no field `{}`", lit.value())),
+                    None => syn::Error::new_spanned(
+                        name,
+                        format!(
+                            "FallibleError can't build a simulated failure: `{name}` has no `message` field; \
+                             add one or pick a field with `#[fallible(message_field = \"...\")]`"
+                        ),
+                    ),
+                };
+                return err.to_compile_error().into();
             }
-            Fields::Unit => {
-                quote! { Self }
+            match fields_init(fields, message, member.as_ref(), &generics, &mut bounds) {
+                Ok(init) => quote! { Self #init },
+                Err(err) => return err.to_compile_error().into(),
             }
-        },
+        }
         Data::Enum(data_enum) => {
-            let fallible_variant = data_enum.variants.iter().find(|v| {
-                v.attrs.iter().any(|attr| {
-                    attr.path().is_ident("fallible") && matches!(&attr.meta, Meta::Path(_))
+            let marked: Vec<_> = data_enum
+                .variants
+                .iter()
+                .filter(|v| {
+                    v.attrs.iter().any(|attr| {
+                        attr.path().is_ident("fallible") && matches!(&attr.meta, Meta::Path(_))
+                    })
                 })
-            });
-
-            let variant = fallible_variant.or_else(|| data_enum.variants.first());
+                .collect();
 
-            if let Some(v) = variant {
-                let variant_name = &v.ident;
-                match &v.fields {
-                    Fields::Named(_) => {
-                        if let Some(msg) = custom_message {
-                            quote! { Self::#variant_name { message: #msg.to_string() } }
-                        } else {
-                            quote! { Self::#variant_name { message: "simulated failure".to_string() } }
-                        }
-                    }
-                    Fields::Unnamed(fields) => {
-                        if fields.unnamed.len() == 1 {
-                            if let Some(msg) = custom_message {
-                                quote! { Self::#variant_name(#msg.to_string()) }
-                            } else {
-                                quote! { Self::#variant_name("simulated failure".to_string()) }
-                            }
-                        } else {
-                            quote! { Self::#variant_name(Default::default()) }
-                        }
+            let chosen_variants: Vec<&syn::Variant> = if marked.is_empty() {
+                data_enum.variants.first().into_iter().collect()
+            } else {
+                marked.clone()
+            };
+            let chosen = if marked.len() > 1 {
+                // Several marked variants are handed out in turn.
+                let mut arms = Vec::with_capacity(marked.len());
+                for (i, v) in marked.iter().enumerate() {
+                    match variant_constructor(v, message, field_name, true, &generics, &mut bounds) {
+                        Ok(expr) => arms.push(quote! { #i => #expr, }),
+                        Err(err) => return err.to_compile_error().into(),
                     }
-                    Fields::Unit => {
-                        quote! { Self::#variant_name }
+                }
+                let count = marked.len();
+                quote! {
+                    static NEXT: ::core::sync::atomic::AtomicUsize = ::core::sync::atomic::AtomicUsize::new(0);
+                    match NEXT.fetch_add(1, ::core::sync::atomic::Ordering::Relaxed) % #count {
+                        #(#arms)*
+                        _ => unreachable!(),
                     }
                 }
+            } else if let Some(v) = chosen_variants.first() {
+                match variant_constructor(v, message, field_name, !marked.is_empty(), &generics, &mut bounds) {
+                    Ok(expr) => expr,
+                    Err(err) => return err.to_compile_error().into(),
+                }
             } else {
                 quote! { panic!("No variants in enum") }
+            };
+
+            // `Outcome::ErrVariant` can ask for any variant that can be built. Ones
+            // that need bounds of their own would narrow the impl, so only those
+            // already chosen above are offered.
+            let requested = data_enum.variants.iter().filter_map(|v| {
+                let mut needed = Vec::new();
+                let expr = variant_constructor(v, message, field_name, false, &generics, &mut needed).ok()?;
+                if !needed.is_empty() && !chosen_variants.iter().any(|chosen| chosen.ident == v.ident) {
+                    return None;
+                }
+                let name = v.ident.to_string();
+                Some(quote! { ::core::option::Option::Some(#name) => return #expr, })
+            });
+            let requested = quote! {
+                match #krate::requested_variant() {
+                    #(#requested)*
+                    _ => {}
+                }
+            };
+            quote! {
+                #requested
+                #chosen
             }
         }
         Data::Union(_) => {
@@ -377,12 +1725,36 @@ pub fn derive_fallible_error(input: TokenStream) -> TokenStream {
         }
     };
 
+    let error_impls = derive_error.then(|| {
+        let display = display_body(&input, custom_message.as_deref(), field_name);
+        let display_bounds = display_bounds(&input, field_name, &generics);
+        let display_generics = with_bounds(&input.generics, display_bounds.clone());
+        let (impl_generics, ty_generics, where_clause) = display_generics.split_for_impl();
+        let mut error_bounds = display_bounds;
+        error_bounds.push(syn::parse_quote! { Self: ::core::fmt::Debug });
+        let error_generics = with_bounds(&input.generics, error_bounds);
+        let (_, _, error_where_clause) = error_generics.split_for_impl();
+        quote! {
+            impl #impl_generics ::core::fmt::Display for #name #ty_generics #where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #display
+                }
+            }
+
+            impl #impl_generics ::core::error::Error for #name #ty_generics #error_where_clause {}
+        }
+    });
+
+    let impl_generics = with_bounds(&input.generics, bounds);
+    let (impl_generics, ty_generics, where_clause) = impl_generics.split_for_impl();
     let expanded = quote! {
-        impl #impl_generics ::fallibles::fallibles_core::FallibleError for #name #ty_generics #where_clause {
+        impl #impl_generics #krate::FallibleError for #name #ty_generics #where_clause {
             fn simulated_failure() -> Self {
                 #error_expr
             }
         }
+
+        #error_impls
     };
 
     expanded.into()
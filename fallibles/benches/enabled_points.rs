@@ -0,0 +1,33 @@
+use fallibles::fallibles_core::*;
+use std::hint::black_box;
+use std::time::Instant;
+
+/// times checks against configs with a growing number of enabled points
+/// (run with `cargo bench --bench enabled_points`)
+fn main() {
+    println!("enabled point lookups:\n");
+
+    for enabled in [1u32, 10, 100, 1000] {
+        let base = FailureConfig::enable_all().force_in_release(true);
+        let config = (0..enabled).fold(base, |config, i| {
+            config.enable_point(FailurePointId(i * 2))
+        });
+        let _guard = with_thread_config(config);
+
+        let checks = 1_000_000u32;
+        let start = Instant::now();
+        let mut failures = 0;
+        for i in 0..checks {
+            let fp = FailurePoint::new(FailurePointId(i % (enabled * 2)), "lookup");
+            failures += should_simulate_failure(black_box(fp)) as u32;
+        }
+        let elapsed = start.elapsed();
+
+        println!(
+            "   {:>6} points: {:>7.1}ns per check ({} failures)",
+            enabled,
+            elapsed.as_nanos() as f64 / checks as f64,
+            failures
+        );
+    }
+}
@@ -60,6 +60,9 @@ fn main() {
         println!();
     } // config gets cleared here
 
+    // inline attributes apply while any config is installed
+    let inline_guard = fallibles_core::with_config(fallibles_core::FailureConfig::new());
+
     println!("\n4. inline probability:");
     for i in 0..20 {
         match fetch_data() {
@@ -79,6 +82,7 @@ fn main() {
             Err(_) => println!("   Attempt {}: FAILED", i),
         }
     }
+    drop(inline_guard);
 
     println!("\n6. seeded (seed = 99999):");
     {
@@ -155,7 +159,7 @@ fn main() {
         );
 
         let start = Instant::now();
-        for i in 0..5 {
+        for _ in 0..5 {
             match read_config() {
                 Ok(_) => print!("."),
                 Err(_) => print!("X"),
@@ -172,7 +176,7 @@ fn main() {
             .max_failures(3);
         let _guard = fallibles_core::with_config(config);
 
-        for i in 0..15 {
+        for _ in 0..15 {
             match read_config() {
                 Ok(_) => print!("."),
                 Err(_) => print!("X"),
@@ -25,6 +25,7 @@
 //! ## Basic Usage
 //!
 //! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
 //! use fallibles::*;
 //!
 //! #[fallible]
@@ -47,8 +48,13 @@
 //!
 //! ## Inline Configuration
 //!
+//! Inline attributes take effect while a config is installed, so clearing it
+//! stops them like any other failure point.
+//!
 //! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
 //! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
 //!
 //! #[fallible(probability = 0.2)]  // 20% failure rate
 //! fn flaky_api() -> Result<String, &'static str> {
@@ -59,6 +65,637 @@
 //! fn periodic_task() -> Result<(), String> {
 //!     Ok(())
 //! }
+//!
+//! let guard = with_config(FailureConfig::new());
+//! assert!(periodic_task().is_err());
+//! assert!(periodic_task().is_ok());
+//! drop(guard);
+//! ```
+//!
+//! ```rust,standalone_crate
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, clear_failure_config, configure_failures};
+//!
+//! #[fallible(probability = 1.0)]
+//! fn flaky_api() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! configure_failures(FailureConfig::new());
+//! assert!(flaky_api().is_err());
+//!
+//! clear_failure_config();
+//! assert!(flaky_api().is_ok());
+//! ```
+//!
+//! A config built with `override_inline(true)` takes precedence over inline attributes:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! #[fallible(probability = 0.2)]
+//! fn flaky_api() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0).override_inline(true));
+//! assert!((0..50).all(|_| flaky_api().is_err()));
+//! ```
+//!
+//! An inline probability fails on exactly the same calls as the same probability
+//! set on the config:
+//!
+//! ```rust
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_thread_config};
+//!
+//! #[fallible(probability = 0.3)]
+//! fn fetch_quote() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! let run = |config: FailureConfig| {
+//!     let _guard = with_thread_config(config.with_seed(7));
+//!     (0..500).map(|_| fetch_quote().is_err()).collect::<Vec<_>>()
+//! };
+//! let inline = run(FailureConfig::new());
+//! let config = run(FailureConfig::new().with_probability(0.3).override_inline(true));
+//! assert_eq!(inline, config);
+//! ```
+//!
+//! `offset` staggers `trigger_every` points so they don't all fail on the same call:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_thread_config};
+//!
+//! #[fallible(trigger_every = 5)]  // Fails on calls 0, 5, 10, ...
+//! fn read_block() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! #[fallible(trigger_every = 5, offset = 2)]  // Fails on calls 2, 7, 12, ...
+//! fn write_block() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! let _guard = with_thread_config(FailureConfig::new());
+//! let reads: Vec<usize> = (0..10).filter(|_| read_block().is_err()).collect();
+//! let writes: Vec<usize> = (0..10).filter(|_| write_block().is_err()).collect();
+//! assert_eq!(reads, [0, 5]);
+//! assert_eq!(writes, [2, 7]);
+//! ```
+//!
+//! `probability`, `trigger_every` and `enabled` also take consts and other
+//! expressions, evaluated on each call, so rates can live in one place:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_thread_config};
+//! use std::sync::atomic::{AtomicBool, Ordering};
+//!
+//! const PROB: f64 = 0.3;
+//! static STORAGE_CHAOS: AtomicBool = AtomicBool::new(false);
+//!
+//! #[fallible(probability = PROB)]
+//! fn fetch_quote() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! #[fallible(enabled = STORAGE_CHAOS.load(Ordering::Relaxed))]
+//! fn write_block() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! let run = |config: FailureConfig| {
+//!     let _guard = with_thread_config(config.with_seed(7));
+//!     (0..500).map(|_| fetch_quote().is_err()).collect::<Vec<_>>()
+//! };
+//! let inline = run(FailureConfig::new());
+//! assert_eq!(inline, run(FailureConfig::new().with_probability(0.3).override_inline(true)));
+//!
+//! let _guard = with_thread_config(FailureConfig::new().with_probability(1.0));
+//! assert!(write_block().is_ok());
+//! STORAGE_CHAOS.store(true, Ordering::Relaxed);
+//! assert!(write_block().is_err());
+//! ```
+//!
+//! ## Failing After Side Effects
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::sync::atomic::{AtomicU32, Ordering};
+//!
+//! static SENT: AtomicU32 = AtomicU32::new(0);
+//!
+//! // The check runs just before `Ok(..)`, so the message is already sent
+//! #[fallible(position = "end")]
+//! fn send(message: &str) -> Result<usize, &'static str> {
+//!     SENT.fetch_add(1, Ordering::SeqCst);
+//!     Ok(message.len())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert!(send("hello").is_err());
+//! assert_eq!(SENT.load(Ordering::SeqCst), 1);
+//! ```
+//!
+//! ## Steps Inside a Function
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! fn provision() -> Result<Vec<&'static str>, String> {
+//!     let mut done = Vec::new();
+//!     fallible_step!("create_vm");
+//!     done.push("vm");
+//!     fallible_step!("attach_disk");
+//!     done.push("disk");
+//!     fallible_step!("boot");
+//!     done.push("boot");
+//!     Ok(done)
+//! }
+//!
+//! assert_eq!(provision().unwrap(), ["vm", "disk", "boot"]);
+//!
+//! // Only the second step fails
+//! let guard = with_config(FailureConfig::enable_all().enable_named("attach_disk"));
+//! let err = provision().unwrap_err();
+//! assert!(err.contains("attach_disk"));
+//! drop(guard);
+//!
+//! let _guard = with_config(FailureConfig::enable_all().enable_named("boot"));
+//! assert!(provision().unwrap_err().contains("boot"));
+//! ```
+//!
+//! ## Targeting One Function
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! #[fallible]
+//! fn database_query() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! #[fallible]
+//! fn cache_lookup() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::enable_all().enable_named("database_query"));
+//! assert!(database_query().is_err());
+//! assert!(cache_lookup().is_ok());
+//! ```
+//!
+//! The id can also be exported as a constant:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! // Also emits `READ_MANIFEST_FAILURE_ID`
+//! #[fallible(export_id)]
+//! fn read_manifest() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! #[fallible]
+//! fn write_manifest() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::enable_all().enable_point(READ_MANIFEST_FAILURE_ID));
+//! assert!(read_manifest().is_err());
+//! assert!(write_manifest().is_ok());
+//! ```
+//!
+//! Ids are derived from the function name alone, so same-named functions in different
+//! modules or crates share one id and are enabled together. Give them a `namespace` to
+//! keep them apart, and enable them by the qualified name:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! mod billing {
+//!     use fallibles::fallible;
+//!
+//!     #[fallible(namespace = "billing")]
+//!     pub fn query() -> Result<(), &'static str> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! mod search {
+//!     use fallibles::fallible;
+//!
+//!     #[fallible(namespace = "search", export_id)]
+//!     pub fn query() -> Result<(), &'static str> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! assert_ne!(search::QUERY_FAILURE_ID, FailurePointId::from_name("query"));
+//! assert_eq!(search::QUERY_FAILURE_ID, FailurePointId::from_name("search::query"));
+//!
+//! let _guard = with_config(FailureConfig::enable_all().enable_named("billing::query"));
+//! assert!(billing::query().is_err());
+//! assert!(search::query().is_ok());
+//! ```
+//!
+//! Points marked `enabled = false` stay quiet under broad configs and only fail once
+//! enabled by name. `off` compiles the check out instead.
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, call_count, with_thread_config};
+//!
+//! #[fallible(enabled = false)]
+//! fn migrate_schema() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! #[fallible(off)]
+//! fn hot_path() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! {
+//!     let _guard = with_thread_config(FailureConfig::enable_all());
+//!     assert!(migrate_schema().is_ok());
+//!     assert!(hot_path().is_ok());
+//! }
+//!
+//! let _guard = with_thread_config(
+//!     FailureConfig::enable_all()
+//!         .enable_named("migrate_schema")
+//!         .enable_named("hot_path"),
+//! );
+//! assert!(migrate_schema().is_err());
+//! assert!(hot_path().is_ok());
+//!
+//! // Still registered while quiet; the compiled-out point never is
+//! assert_eq!(call_count(FailurePointId::from_name("migrate_schema")), 2);
+//! assert_eq!(call_count(FailurePointId::from_name("hot_path")), 0);
+//! ```
+//!
+//! ## Call Counts
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, call_count, with_config};
+//!
+//! #[fallible(trigger_every = 3, export_id)]
+//! fn rotate_logs() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new());
+//! let failures = (0..7).filter(|_| rotate_logs().is_err()).count();
+//! assert_eq!(failures, 3);
+//! assert_eq!(call_count(ROTATE_LOGS_FAILURE_ID), 7);
+//! ```
+//!
+//! ## Futures Without `async fn`
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::future::Future;
+//! use std::pin::{Pin, pin};
+//! use std::task::{Context, Poll, Waker};
+//!
+//! #[fallible]
+//! fn fetch() -> impl Future<Output = Result<u32, &'static str>> {
+//!     async { Ok(7) }
+//! }
+//!
+//! #[fallible]
+//! fn fetch_boxed() -> Pin<Box<dyn Future<Output = Result<u32, &'static str>> + Send>> {
+//!     Box::pin(async { Ok(7) })
+//! }
+//!
+//! let mut cx = Context::from_waker(Waker::noop());
+//! assert_eq!(pin!(fetch()).poll(&mut cx), Poll::Ready(Ok(7)));
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert!(matches!(pin!(fetch()).poll(&mut cx), Poll::Ready(Err(_))));
+//! assert!(matches!(fetch_boxed().as_mut().poll(&mut cx), Poll::Ready(Err(_))));
+//! ```
+//!
+//! ## When Async Checks Run
+//!
+//! An `async fn` checks its point, counts the call and draws its `probability` or
+//! `trigger_every` decision when the future is first polled, like the rest of its
+//! body. A function returning `impl Future` or a boxed future checks when it is
+//! called, before the future exists. Either way the check runs once per call:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, call_count, with_thread_config};
+//! use std::future::Future;
+//! use std::pin::pin;
+//! use std::task::{Context, Poll, Waker};
+//!
+//! #[fallible(trigger_every = 2, export_id)]
+//! async fn flush() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! #[fallible(trigger_every = 2, export_id)]
+//! fn flush_eager() -> impl Future<Output = Result<(), &'static str>> {
+//!     async { Ok(()) }
+//! }
+//!
+//! let _guard = with_thread_config(FailureConfig::new());
+//! let mut cx = Context::from_waker(Waker::noop());
+//!
+//! // Counted on first poll: the future polled first gets call 0 and fails
+//! let (mut first, mut second) = (pin!(flush()), pin!(flush()));
+//! assert_eq!(call_count(FLUSH_FAILURE_ID), 0);
+//! assert_eq!(second.as_mut().poll(&mut cx), Poll::Ready(Err("simulated failure")));
+//! assert_eq!(first.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+//! assert_eq!(call_count(FLUSH_FAILURE_ID), 2);
+//!
+//! // Counted on call: the future created first gets call 0 and fails
+//! let (mut first, mut second) = (pin!(flush_eager()), pin!(flush_eager()));
+//! assert_eq!(call_count(FLUSH_EAGER_FAILURE_ID), 2);
+//! assert_eq!(second.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+//! assert_eq!(first.as_mut().poll(&mut cx), Poll::Ready(Err("simulated failure")));
+//! ```
+//!
+//! ## Generic Functions
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::str::FromStr;
+//!
+//! struct Port(u16);
+//!
+//! impl FromStr for Port {
+//!     type Err = String;
+//!
+//!     fn from_str(s: &str) -> Result<Self, String> {
+//!         s.parse().map(Port).map_err(|_| format!("bad port {s}"))
+//!     }
+//! }
+//!
+//! // `T::Err: FallibleError` is added to the injected function's bounds
+//! #[fallible]
+//! fn parse<T: FromStr>(input: &str) -> Result<T, T::Err> {
+//!     input.parse()
+//! }
+//!
+//! assert_eq!(parse::<Port>("80").unwrap().0, 80);
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert!(parse::<Port>("80").is_err());
+//! ```
+//!
+//! Lifetimes, `where` clauses and attributes like `#[must_use]` are kept as written:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::str::FromStr;
+//!
+//! # #[derive(Debug, PartialEq)]
+//! # struct Port(u16);
+//! # impl FromStr for Port {
+//! #     type Err = String;
+//! #     fn from_str(s: &str) -> Result<Self, String> {
+//! #         s.parse().map(Port).map_err(|_| format!("bad port {s}"))
+//! #     }
+//! # }
+//! #[fallible]
+//! #[must_use]
+//! fn first<'a, T>(items: &'a [T]) -> Result<T, String>
+//! where
+//!     T: Clone,
+//! {
+//!     items.first().cloned().ok_or_else(|| "empty".to_string())
+//! }
+//!
+//! #[fallible]
+//! fn parse_all<'a, T>(inputs: &[&'a str]) -> Result<Vec<T>, T::Err>
+//! where
+//!     T: FromStr,
+//! {
+//!     inputs.iter().map(|s| s.parse()).collect()
+//! }
+//!
+//! assert_eq!(first(&["a", "b"]), Ok("a"));
+//! assert_eq!(parse_all::<Port>(&["80", "443"]), Ok(vec![Port(80), Port(443)]));
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert!(first(&[1]).is_err());
+//! assert!(parse_all::<Port>(&["80"]).is_err());
+//! ```
+//!
+//! ## Trait Methods
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! trait Store {
+//!     // Default bodies can be marked; implementations inherit the injection
+//!     #[fallible]
+//!     fn load(&self) -> Result<u32, &'static str> {
+//!         Ok(1)
+//!     }
+//! }
+//!
+//! struct Memory;
+//! impl Store for Memory {}
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert!(Memory.load().is_err());
+//! ```
+//!
+//! ## Attributes on Annotated Functions
+//!
+//! Doc comments and attributes stay on the function, even when written below
+//! `#[fallible]`.
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! /// Parse a port number.
+//! #[fallible]
+//! #[inline]
+//! fn parse_port(s: &str) -> Result<u16, String> {
+//!     s.parse().map_err(|_| format!("bad port {s:?}"))
+//! }
+//!
+//! #[fallible]
+//! #[cfg(test)]
+//! fn fixture() -> Result<u32, String> {
+//!     Ok(1)
+//! }
+//!
+//! // `fixture` above is compiled out, so this one doesn't clash with it
+//! fn fixture() -> u32 {
+//!     7
+//! }
+//!
+//! assert_eq!(parse_port("80"), Ok(80));
+//! assert_eq!(fixture(), 7);
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert!(parse_port("80").is_err());
+//! ```
+//!
+//! A `const fn` can't check for failures at runtime, so `#[fallible]` rejects it
+//! while `fallibles-sim` is enabled:
+//!
+//! ```compile_fail
+//! use fallibles::*;
+//!
+//! // error: `#[fallible]` cannot be used on `const fn`
+//! #[fallible]
+//! const fn max_retries() -> Result<u32, String> {
+//!     Ok(3)
+//! }
+//! ```
+//!
+//! ## Unsafe Functions
+//!
+//! `unsafe fn` keeps its qualifier, and the check runs before the body, so an
+//! injected failure returns before any unsafe operation:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! /// # Safety
+//! /// `ptr` must be valid for reads.
+//! #[fallible]
+//! unsafe fn read_word(ptr: *const u32) -> Result<u32, &'static str> {
+//!     Ok(unsafe { ptr.read() })
+//! }
+//!
+//! let word = 7;
+//! assert_eq!(unsafe { read_word(&word) }, Ok(7));
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert!(unsafe { read_word(&word) }.is_err());
+//! ```
+//!
+//! `position = "end"` would return after the body's side effects but before its
+//! final expression, so it's rejected on `unsafe fn`:
+//!
+//! ```compile_fail
+//! use fallibles::*;
+//!
+//! /// # Safety
+//! /// `lock` must be held.
+//! #[fallible(position = "end")]  // error: not supported on `unsafe fn`
+//! unsafe fn flush(lock: *mut bool) -> Result<(), &'static str> {
+//!     Ok(unsafe { *lock = false })
+//! }
+//! ```
+//!
+//! ## Functions Generated by Macros
+//!
+//! Every function stamped out by a `macro_rules!` macro reports the macro's own
+//! location. `location` points the `FailurePoint` somewhere more useful instead:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::sync::{Arc, Mutex};
+//!
+//! macro_rules! endpoint {
+//!     ($name:ident, $location:literal) => {
+//!         #[fallible(location = $location)]
+//!         fn $name() -> Result<(), String> {
+//!             Ok(())
+//!         }
+//!     };
+//! }
+//!
+//! endpoint!(list_users, "routes/users.rs:12");
+//! endpoint!(list_orders, "routes/orders.rs:30:5");
+//!
+//! let seen = Arc::new(Mutex::new(Vec::new()));
+//! let log = Arc::clone(&seen);
+//! let _guard = with_config(
+//!     FailureConfig::new()
+//!         .with_probability(1.0)
+//!         .on_failure(move |fp| log.lock().unwrap().push((fp.file, fp.line, fp.column))),
+//! );
+//! assert!(list_users().is_err());
+//! assert!(list_orders().is_err());
+//! assert_eq!(
+//!     *seen.lock().unwrap(),
+//!     [("routes/users.rs", 12, 1), ("routes/orders.rs", 30, 5)]
+//! );
+//! ```
+//!
+//! ## Failing Exactly Once
+//!
+//! ```rust,standalone_crate
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::fail_next;
+//!
+//! #[fallible]
+//! fn commit_transaction() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! // Only the next call on this thread fails
+//! fail_next();
+//! assert!(commit_transaction().is_err());
+//! assert!(commit_transaction().is_ok());
+//! assert!(commit_transaction().is_ok());
+//! ```
+//!
+//! ## Suppressing Failures
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config, without_failures};
+//!
+//! #[fallible(probability = 1.0)]
+//! fn drop_table() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new());
+//! assert!(drop_table().is_err());
+//!
+//! // Teardown runs without injected failures
+//! assert!(without_failures(drop_table).is_ok());
+//! assert!(drop_table().is_err());
 //! ```
 //!
 //! ## Policy-Based Testing
@@ -74,6 +711,18 @@
 //!
 //! // Circuit Breaker: fail every 5th call
 //! let _guard = with_config(FailureConfig::circuit_breaker(5));
+//!
+//! // Deterministic: 20% failures, identical on every run
+//! let _guard = with_config(FailureConfig::deterministic(42, 0.2));
+//!
+//! // API Throttling: bursts of 10 failures after every 40 successes
+//! let _guard = with_config(FailureConfig::aws_throttling());
+//!
+//! // Network Partition: 100 good calls, 20 failing, then recovery
+//! let _guard = with_config(FailureConfig::network_partition(100, 20));
+//!
+//! // Slow Disk: added latency, no errors
+//! let _guard = with_config(FailureConfig::slow_disk());
 //! ```
 //!
 //! ## Conditional Failures
@@ -89,6 +738,47 @@
 //! );
 //! ```
 //!
+//! ## Argument-Based Failures
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! // Only large payments are eligible for injected failures
+//! #[fallible(when = |amount| *amount > 1000)]
+//! fn charge(account: u32, amount: u64) -> Result<u64, &'static str> {
+//!     Ok(amount)
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert!(charge(1, 500).is_ok());
+//! assert!(charge(1, 5000).is_err());
+//! ```
+//!
+//! With `by_args` the arguments themselves pick which calls fail: the probability
+//! roll uses a hash of them instead of the call counter, so an input fails every
+//! time or never, whatever order the calls come in.
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! #[fallible(by_args, probability = 0.5)]
+//! fn lookup(user: u32, region: &str) -> Result<u32, &'static str> {
+//!     Ok(user)
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new().with_seed(7));
+//! let first: Vec<bool> = (0..100).map(|user| lookup(user, "eu").is_err()).collect();
+//! assert!(first.contains(&true) && first.contains(&false));
+//!
+//! let reversed: Vec<bool> = (0..100).rev().map(|user| lookup(user, "eu").is_err()).collect();
+//! assert!(first.iter().eq(reversed.iter().rev()));
+//! assert!((0..10).all(|_| lookup(3, "eu").is_err() == first[3]));
+//! ```
+//!
 //! ## Reproducible Testing
 //!
 //! ```rust
@@ -103,6 +793,408 @@
 //! // Or from environment: FALLIBLE_SEED=12345 cargo test
 //! ```
 //!
+//! ## Sampling a Function
+//!
+//! `sample()` runs a function a number of times under a config and returns the
+//! stats, restoring the previous config afterwards:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, sample};
+//!
+//! #[fallible]
+//! fn lookup_user() -> Result<u32, &'static str> {
+//!     Ok(42)
+//! }
+//!
+//! let config = FailureConfig::new().with_probability(0.25).with_seed(3);
+//! let stats = sample(config, 10_000, lookup_user);
+//!
+//! assert_eq!(stats.total_checks, 10_000);
+//! assert!((2_300..2_700).contains(&stats.total_failures));
+//! ```
+//!
+//! ## Kill Switch
+//!
+//! ```rust,standalone_crate
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, configure_failures, disable_all, enable_all_injection};
+//!
+//! #[fallible(trigger_every = 1)]
+//! fn sync_replica() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! configure_failures(FailureConfig::new());
+//! assert!(sync_replica().is_err());
+//!
+//! // Stops every failure point, inline ones included, on all threads
+//! disable_all();
+//! assert!(sync_replica().is_ok());
+//! enable_all_injection();
+//! assert!(sync_replica().is_err());
+//! ```
+//!
+//! ## Severity Levels
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, Severity, with_config};
+//!
+//! #[fallible(severity = "low")]
+//! fn cache_lookup() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! #[fallible(severity = "critical")]
+//! fn charge_card() -> Result<(), &'static str> {
+//!     Ok(())
+//! }
+//!
+//! // Only inject failures up to high severity
+//! let _guard = with_config(
+//!     FailureConfig::new()
+//!         .with_probability(1.0)
+//!         .max_severity(Severity::High)
+//! );
+//! assert!(cache_lookup().is_err());
+//! assert!(charge_card().is_ok());
+//! ```
+//!
+//! ## Point-Aware Errors
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, FailurePoint, with_config};
+//!
+//! #[derive(Debug)]
+//! struct QueryError(String);
+//!
+//! impl FallibleError for QueryError {
+//!     fn simulated_failure() -> Self {
+//!         QueryError("simulated failure".to_string())
+//!     }
+//!
+//!     // Called with the point that failed
+//!     fn simulated_failure_for(fp: FailurePoint) -> Self {
+//!         QueryError(format!("simulated failure at line {}", fp.line))
+//!     }
+//! }
+//!
+//! #[fallible]
+//! fn run_query() -> Result<(), QueryError> {
+//!     Ok(())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! let err = run_query().unwrap_err();
+//! assert!(err.0.starts_with("simulated failure at line "));
+//! ```
+//!
+//! The built-in `String`, `std::io::Error`, `anyhow` and `eyre` errors already name
+//! the failing function and location:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! #[fallible]
+//! fn load_profile() -> Result<(), String> {
+//!     Ok(())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! let err = load_profile().unwrap_err();
+//! assert!(err.contains("load_profile"));
+//! ```
+//!
+//! The point being failed is also available from `current_point()` while the error
+//! is built, for impls that only provide `simulated_failure()`:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, current_point, with_config};
+//!
+//! #[derive(Debug)]
+//! struct StoreError(String);
+//!
+//! impl FallibleError for StoreError {
+//!     fn simulated_failure() -> Self {
+//!         let origin = current_point().map_or("unknown", |fp| fp.function);
+//!         StoreError(format!("{origin} failed"))
+//!     }
+//! }
+//!
+//! #[fallible]
+//! fn flush() -> Result<(), StoreError> {
+//!     Ok(())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert_eq!(flush().unwrap_err().0, "flush failed");
+//! assert_eq!(StoreError::simulated_failure().0, "unknown failed");
+//! ```
+//!
+//! `SimulatedFailure` carries the point itself, so the origin is still known after
+//! the error has passed through a few `?`:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::error::Error;
+//!
+//! #[fallible]
+//! fn fetch_rows() -> Result<Vec<u32>, SimulatedFailure> {
+//!     Ok(vec![1, 2, 3])
+//! }
+//!
+//! fn total() -> Result<u32, SimulatedFailure> {
+//!     Ok(fetch_rows()?.iter().sum())
+//! }
+//!
+//! fn report() -> Result<String, Box<dyn Error>> {
+//!     Ok(format!("total: {}", total()?))
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! let err = report().unwrap_err();
+//! let origin = err.downcast_ref::<SimulatedFailure>().and_then(|err| err.point).unwrap();
+//! assert_eq!(origin.function, "fetch_rows");
+//! assert_eq!(origin.file, file!());
+//! assert!(err.to_string().starts_with("simulated failure in fetch_rows at "));
+//! ```
+//!
+//! ## Observing Injected Errors
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_thread_config};
+//! use std::sync::{Arc, Mutex};
+//!
+//! #[derive(Debug)]
+//! struct QuotaError {
+//!     tenant: u32,
+//! }
+//!
+//! impl FallibleError for QuotaError {
+//!     fn simulated_failure() -> Self {
+//!         QuotaError { tenant: 7 }
+//!     }
+//! }
+//!
+//! #[fallible]
+//! fn reserve() -> Result<(), QuotaError> {
+//!     Ok(())
+//! }
+//!
+//! let seen = Arc::new(Mutex::new(Vec::new()));
+//! let log = seen.clone();
+//! let _guard = with_thread_config(FailureConfig::enable_all().on_failure_value(move |fp, err| {
+//!     log.lock().unwrap().push(format!("{}: {err:?}", fp.function));
+//! }));
+//!
+//! assert!(reserve().is_err());
+//! assert_eq!(*seen.lock().unwrap(), ["reserve: QuotaError { tenant: 7 }"]);
+//! ```
+//!
+//! ## Scripted Outcomes
+//!
+//! `FailureConfig::sequence()` plays a fixed list of outcomes for one function,
+//! starting over after the last one. `Outcome::ErrVariant` picks the variant a
+//! derived `FallibleError` enum returns:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, Outcome, with_thread_config};
+//!
+//! #[derive(Debug, PartialEq, FallibleError)]
+//! enum FetchError {
+//!     Timeout,
+//!     Refused,
+//!     NotFound { message: String },
+//! }
+//!
+//! #[fallible(export_id)]
+//! fn fetch() -> Result<u32, FetchError> {
+//!     Ok(200)
+//! }
+//!
+//! let _guard = with_thread_config(FailureConfig::new().sequence(
+//!     FETCH_FAILURE_ID,
+//!     vec![
+//!         Outcome::Ok,
+//!         Outcome::Err,
+//!         Outcome::ErrVariant("Refused"),
+//!         Outcome::ErrVariant("NotFound"),
+//!     ],
+//! ));
+//!
+//! let results: Vec<_> = (0..6).map(|_| fetch()).collect();
+//! assert_eq!(
+//!     results,
+//!     [
+//!         Ok(200),
+//!         Err(FetchError::Timeout),
+//!         Err(FetchError::Refused),
+//!         Err(FetchError::NotFound { message: "simulated failure".to_string() }),
+//!         Ok(200),
+//!         Err(FetchError::Timeout),
+//!     ]
+//! );
+//! ```
+//!
+//! ## Poisoned Results
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! // Succeeds with `0` instead of failing
+//! #[fallible(poison = true)]
+//! fn exchange_rate() -> Result<u32, &'static str> {
+//!     Ok(120)
+//! }
+//!
+//! fn convert(amount: u32) -> Result<u32, &'static str> {
+//!     let rate = exchange_rate()?;
+//!     if rate == 0 {
+//!         return Err("invalid exchange rate");
+//!     }
+//!     Ok(amount * rate / 100)
+//! }
+//!
+//! assert_eq!(convert(10), Ok(12));
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert_eq!(exchange_rate(), Ok(0));
+//! assert_eq!(convert(10), Err("invalid exchange rate"));
+//! ```
+//!
+//! ## Default Errors
+//!
+//! Error types that already implement `Default` can skip `FallibleError`:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//!
+//! #[derive(Debug, Default, PartialEq)]
+//! enum CacheError {
+//!     #[default]
+//!     Unavailable,
+//!     Corrupt,
+//! }
+//!
+//! #[fallible(default_error)]
+//! fn warm_cache() -> Result<(), CacheError> {
+//!     Ok(())
+//! }
+//!
+//! assert_eq!(warm_cache(), Ok(()));
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert_eq!(warm_cache(), Err(CacheError::Unavailable));
+//! ```
+//!
+//! ## Boxed Errors
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::error::Error;
+//!
+//! #[fallible]
+//! fn parse_count(input: &str) -> Result<i32, Box<dyn Error + Send + Sync>> {
+//!     Ok(input.parse()?)
+//! }
+//!
+//! assert_eq!(parse_count("3").unwrap(), 3);
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! let err = parse_count("3").unwrap_err();
+//! assert!(err.to_string().contains("parse_count"));
+//! ```
+//!
+//! ## `Result` Aliases
+//!
+//! `anyhow::Result<T>` and `eyre::Result<T>` are recognized by name, with the
+//! `anyhow` or `eyre` feature providing their `FallibleError` impls. Other
+//! single-argument aliases name their error type with `error`:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::io;
+//!
+//! #[fallible(error = io::Error)]
+//! fn read_header() -> io::Result<[u8; 4]> {
+//!     Ok(*b"FALL")
+//! }
+//!
+//! assert_eq!(read_header().unwrap(), *b"FALL");
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert_eq!(read_header().unwrap_err().kind(), io::ErrorKind::Other);
+//! ```
+//!
+//! ## `ControlFlow`
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::ops::ControlFlow;
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Stop {
+//!     Budget,
+//! }
+//!
+//! #[fallible(break_with = Stop::Budget)]
+//! fn visit(node: u32) -> ControlFlow<Stop, u32> {
+//!     ControlFlow::Continue(node * 2)
+//! }
+//!
+//! assert_eq!(visit(4), ControlFlow::Continue(8));
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! assert_eq!(visit(4), ControlFlow::Break(Stop::Budget));
+//! ```
+//!
+//! Without `break_with` the break value is built with `FallibleError`:
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::*;
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use std::ops::ControlFlow;
+//!
+//! #[fallible]
+//! fn walk(depth: u32) -> ControlFlow<String> {
+//!     if depth > 3 {
+//!         return ControlFlow::Break(format!("too deep at {depth}"));
+//!     }
+//!     ControlFlow::Continue(())
+//! }
+//!
+//! let _guard = with_config(FailureConfig::new().with_probability(1.0));
+//! let ControlFlow::Break(reason) = walk(1) else { panic!("expected a break") };
+//! assert!(reason.contains("walk"));
+//! ```
+//!
 //! ## Custom Error Types
 //!
 //! ```rust
@@ -126,9 +1218,53 @@
 //! - `std` - Standard library support (default)
 //! - `anyhow` - Support for anyhow::Error
 //! - `eyre` - Support for eyre::Report
+//! - `serde` - Serialize/deserialize `FailureSnapshot` for reproducing runs
+//! - `tracing` - Emit `tracing` events for checks and injected failures
+//! - `metrics` - Count checks and failures as `fallible.checks.total` / `fallible.failures.total`
+//! - `tokio` - Await injected latency in `async fn` with `tokio::time::sleep`
+//! - `json` - Write injected failures as JSON lines with `FailureConfig::log_json`
+//! - `registry` - List every `#[fallible]` point in the program with `all_points()`
 
 pub use fallibles_core::*;
 pub use fallibles_macro::*;
 
 pub extern crate fallibles_core;
-pub extern crate fxhash;
\ No newline at end of file
+pub extern crate fxhash;
+
+/// The attribute, the derive and the items most setups need.
+///
+/// `use fallibles::*;` also brings in every public item of `fallibles_core`;
+/// the prelude is the curated subset.
+///
+/// # Example
+/// ```
+/// # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+/// use fallibles::prelude::*;
+///
+/// #[derive(Debug, FallibleError)]
+/// #[fallible(message = "connection reset")]
+/// struct NetError {
+///     message: String,
+/// }
+///
+/// #[fallible]
+/// fn send(bytes: &[u8]) -> Result<usize, NetError> {
+///     Ok(bytes.len())
+/// }
+///
+/// fn report(fp: FailurePoint) -> &'static str {
+///     fp.function
+/// }
+///
+/// assert_eq!(send(b"ping").unwrap(), 4);
+///
+/// let _guard = with_config(FailureConfig::new().with_probability(1.0).on_failure(|fp| {
+///     assert_eq!(report(fp), "send");
+/// }));
+/// assert_eq!(send(b"ping").unwrap_err().message, "connection reset");
+/// assert_eq!(NetError::simulated_failure().message, "connection reset");
+/// ```
+pub mod prelude {
+    pub use fallibles_core::prelude::*;
+    pub use fallibles_macro::{FallibleError, fallible};
+}
\ No newline at end of file
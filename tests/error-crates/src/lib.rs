@@ -0,0 +1,27 @@
+//! Checks that `#[fallible]` injects into functions returning the `anyhow::Result<T>`
+//! and `eyre::Result<T>` aliases, which only name the ok type.
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use fallibles_error_crates_test::{load_anyhow, load_eyre};
+//!
+//! assert_eq!(load_anyhow().unwrap(), 1);
+//! assert_eq!(load_eyre().unwrap(), 2);
+//!
+//! let _guard = with_config(FailureConfig::enable_all());
+//! assert!(load_anyhow().unwrap_err().to_string().contains("load_anyhow"));
+//! assert!(load_eyre().unwrap_err().to_string().contains("load_eyre"));
+//! ```
+
+use fallibles::fallible;
+
+#[fallible]
+pub fn load_anyhow() -> anyhow::Result<i32> {
+    Ok(1)
+}
+
+#[fallible]
+pub fn load_eyre() -> eyre::Result<i32> {
+    Ok(2)
+}
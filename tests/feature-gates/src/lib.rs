@@ -0,0 +1,32 @@
+//! Checks that `#[fallible(feature = "..")]` only injects when the named feature is
+//! enabled in this crate, on top of `fallibles-sim`. The defaults enable `chaos_db`
+//! but not `chaos_cache`.
+//!
+//! ```rust
+//! # if !fallibles::fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles::fallibles_core::{FailureConfig, with_config};
+//! use fallibles_feature_gates_test::{cache_get, db_query, render};
+//!
+//! let _guard = with_config(FailureConfig::enable_all());
+//!
+//! assert_eq!(render().is_err(), cfg!(feature = "fallibles-sim"));
+//! assert_eq!(db_query().is_err(), cfg!(all(feature = "fallibles-sim", feature = "chaos_db")));
+//! assert_eq!(cache_get().is_err(), cfg!(all(feature = "fallibles-sim", feature = "chaos_cache")));
+//! ```
+
+use fallibles::fallible;
+
+#[fallible(feature = "chaos_db")]
+pub fn db_query() -> Result<u32, &'static str> {
+    Ok(1)
+}
+
+#[fallible(feature = "chaos_cache")]
+pub fn cache_get() -> Result<u32, &'static str> {
+    Ok(2)
+}
+
+#[fallible]
+pub fn render() -> Result<u32, &'static str> {
+    Ok(3)
+}
@@ -0,0 +1,37 @@
+//! Checks that `#[fallible]` works in a `#![no_std]` crate that depends on
+//! `fallibles-core` and `fallibles-macro` directly, without the `fallibles` facade.
+//!
+//! ```rust
+//! # if !fallibles_core::__private::DEBUG_ASSERTIONS { return; }
+//! use fallibles_core::{FailureConfig, clear_failure_config, configure_failures};
+//! use fallibles_no_std_test::{ParseError, parse_flag, read_register};
+//!
+//! assert_eq!(read_register(7), Ok(7));
+//! assert_eq!(parse_flag(1), Ok(true));
+//!
+//! configure_failures(FailureConfig::enable_all());
+//! assert_eq!(read_register(7), Err("simulated failure"));
+//! assert_eq!(parse_flag(1), Err(ParseError));
+//! clear_failure_config();
+//! ```
+
+#![no_std]
+
+use fallibles_macro::{FallibleError, fallible};
+
+#[derive(Debug, PartialEq, FallibleError)]
+#[fallible(crate = fallibles_core)]
+pub struct ParseError;
+
+#[fallible(crate = fallibles_core)]
+pub fn read_register(addr: u8) -> Result<u8, &'static str> {
+    Ok(addr)
+}
+
+#[fallible(crate = ::fallibles_core)]
+pub fn parse_flag(byte: u8) -> core::result::Result<bool, ParseError> {
+    match byte {
+        0 => Ok(false),
+        _ => Ok(true),
+    }
+}
@@ -0,0 +1,28 @@
+//! Checks that `#[fallible]` functions show up in `all_points()` before they are
+//! ever called.
+//!
+//! ```rust
+//! use fallibles::fallibles_core::all_points;
+//! // Nothing else here names this crate, so link it explicitly
+//! use fallibles_registry_test as _;
+//!
+//! let points = all_points();
+//! let functions: Vec<_> = points.iter().map(|fp| fp.function).collect();
+//! assert!(functions.contains(&"load_orders"));
+//! assert!(functions.contains(&"save_order"));
+//!
+//! let save = points.iter().find(|fp| fp.function == "save_order").unwrap();
+//! assert!(save.file.ends_with("lib.rs"));
+//! ```
+
+use fallibles::fallible;
+
+#[fallible]
+pub fn load_orders() -> Result<Vec<u32>, String> {
+    Ok(vec![1, 2])
+}
+
+#[fallible(severity = "high")]
+pub fn save_order(id: u32) -> Result<u32, String> {
+    Ok(id)
+}
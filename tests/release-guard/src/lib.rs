@@ -0,0 +1,31 @@
+//! Checks that builds of `fallibles-core` without `debug_assertions` refuse to
+//! install configs that weren't built with `force_in_release(true)`.
+//!
+//! This crate has its own workspace so the override only applies here; run it with
+//! `cargo test --manifest-path tests/release-guard/Cargo.toml`.
+//!
+//! ```rust
+//! use fallibles_core::*;
+//!
+//! // The profile override took effect
+//! assert!(!fallibles_core::__private::DEBUG_ASSERTIONS);
+//!
+//! let fp = FailurePoint::new(FailurePointId(1), "checkout");
+//!
+//! configure_failures(FailureConfig::enable_all());
+//! assert!(!is_global_active());
+//! set_default_config(FailureConfig::enable_all());
+//! configure_thread_failures(FailureConfig::enable_all());
+//! assert!(!is_thread_active());
+//! {
+//!     let _global = with_config(FailureConfig::enable_all());
+//!     let _thread = with_thread_config(FailureConfig::enable_all());
+//!     assert!(!is_active());
+//!     assert!(!should_simulate_failure(fp));
+//! }
+//! assert!(!should_simulate_failure(fp));
+//!
+//! configure_failures(FailureConfig::enable_all().force_in_release(true));
+//! assert!(is_global_active());
+//! assert!(should_simulate_failure(fp));
+//! ```